@@ -7,19 +7,37 @@
 mod state;
 
 use log::info;
-use self::state::LeaderboardState;
+use self::state::{
+    head_to_head_key, processed_key, rank_of_key, BoardStats, BoardView, LeaderboardState, MatchDelta, PlayerDelta,
+    PlayerResult, RankKey, ResultHistory, WinLoss,
+};
 use linera_sdk::{
     abi::WithContractAbi,
-    views::{RootView, View},
+    linera_base_types::ApplicationId,
+    views::{MapView, RootView, View},
     Contract, ContractRuntime,
 };
-use leaderboard::{LeaderboardAbi, Operation, RecordScoreMessage};
+use leaderboard::{
+    BoardConfig, BoardInfo, DecayEvent, DuplicateResult, LeaderboardAbi, LeaderboardConfig, LeaderboardResponse,
+    LeaderboardSnapshot, MatchCorrection, MatchOutcome, Message, Operation, Parameters, PlayerAlias, PlayerGroup,
+    PlayerSeasonRecord, PlayerStanding, RankedEntry, RejectedWrite, ScorePoint, SeasonSummary, StreakState,
+    TypedRecordScoreMessage,
+};
+use leaderboard::integrity::{FlaggedMatch, PairMatch, PAIR_HISTORY_LEN};
+use leaderboard::rating::{self, INITIAL_RATING};
+use leaderboard::scoring::{ScoringFormula, ScoringKind};
+use std::collections::HashSet;
 
 linera_sdk::contract!(LeaderboardContract);
 
+/// Số nửa kết quả chờ ghép tối đa của một người chơi; quá thì nửa cũ nhất bị bỏ (không tính rating / đối đầu).
+const MAX_PENDING_PER_USER: usize = 50;
+
+/// Số kết quả gần nhất được giữ trong `ResultHistory`; kết quả cũ hơn được gộp vào `base`.
+const RESULT_HISTORY_LEN: usize = 100;
+
 pub struct LeaderboardContract {
     state: LeaderboardState,
-    #[allow(dead_code)]
     runtime: ContractRuntime<Self>,
 }
 
@@ -28,9 +46,9 @@ impl WithContractAbi for LeaderboardContract {
 }
 
 impl Contract for LeaderboardContract {
-    type Parameters = ();
-    type InstantiationArgument = ();
-    type Message = RecordScoreMessage;
+    type Parameters = Parameters;
+    type InstantiationArgument = Option<LeaderboardConfig>; // null = cấu hình mặc định
+    type Message = Message;
     type EventValue = ();
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
@@ -40,8 +58,13 @@ impl Contract for LeaderboardContract {
         Self { state, runtime }
     }
 
-    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
+    async fn instantiate(&mut self, argument: Self::InstantiationArgument) {
         // Khởi tạo trạng thái
+        let mut config = argument.unwrap_or_default();
+        config.tiers.normalize();
+        info!("[LEADERBOARD] Instantiate with config: {:?}", config);
+        self.state.config.set(config);
+        self.state.current_season.set(1);
     }
 
     async fn store(mut self) {
@@ -51,30 +74,755 @@ impl Contract for LeaderboardContract {
     /// Xử lý operation từ service
     async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
         match operation {
-            Operation::RecordScore { user_id, is_winner, match_id } => {
-                // Luôn xử lý và ghi nhận kết quả.
-                // Việc kiểm tra trùng lặp đã được xử lý ở layer cao hơn (xfighter)
-                // đảm bảo mỗi trận đấu chỉ được gửi một lần.
-		info!("[LEADERBOARD] Received Operation::RecordScore user={} is_winner={} match_id={}",user_id, is_winner, match_id);
-                self.update_score_and_stats(user_id, is_winner, match_id).await;
+            Operation::RecordScore { user_id, is_winner, match_id, match_type, map_name } => {
+		info!("[LEADERBOARD] Received Operation::RecordScore user={} is_winner={} match_id={} match_type={}",user_id, is_winner, match_id, match_type);
+                let result = TypedRecordScoreMessage { user_id, is_winner, match_id, match_type, map_name };
+                if self.operation_writer_allowed().await {
+                    self.record_once(result, "operation").await;
+                } else {
+                    self.reject("RecordScore", "operation", Some(&result.match_id), Some(&result.user_id));
+                }
+            }
+            Operation::RecordMatch(outcome) => {
+                info!("[LEADERBOARD] Received Operation::RecordMatch match_id={} winner={} loser={}", outcome.match_id, outcome.winner, outcome.loser);
+                if self.operation_writer_allowed().await {
+                    self.record_outcome(outcome, "operation").await;
+                } else {
+                    self.reject("RecordMatch", "operation", Some(&outcome.match_id), None);
+                }
+            }
+            Operation::CloseSeason => {
+                if self.ensure_admin("CloseSeason") {
+                    self.close_season().await;
+                }
+            }
+            Operation::RebuildRankingIndex => {
+                if self.ensure_admin("RebuildRankingIndex") {
+                    self.rebuild_ranking_index().await;
+                }
+            }
+            Operation::VoidMatch { match_id } => {
+                if self.ensure_admin("VoidMatch") {
+                    let previous = self.void_match(&match_id).await;
+                    self.log_correction(&match_id, "void", previous.as_ref(), None);
+                }
+            }
+            Operation::CorrectMatch { match_id, winner, loser } => {
+                if self.ensure_admin("CorrectMatch") {
+                    let previous = self.correct_match(&match_id, &winner, &loser).await;
+                    self.log_correction(&match_id, "correct", previous.as_ref(), Some((winner.as_str(), loser.as_str())));
+                }
+            }
+            Operation::MergePlayers { from, to } => {
+                if self.ensure_admin("MergePlayers") {
+                    self.merge_players(&from, &to, "merge").await;
+                }
+            }
+            Operation::RenamePlayer { from, to } => {
+                if self.ensure_admin("RenamePlayer") {
+                    let to = self.state.resolve(&to).await;
+                    if self.state.is_known(&to).await {
+                        info!("[LEADERBOARD] RenamePlayer: {} đã có dữ liệu, dùng MergePlayers", to);
+                    } else {
+                        self.merge_players(&from, &to, "rename").await;
+                    }
+                }
+            }
+            Operation::SaveGroup { group_id, name, kind, members } => {
+                if self.operation_writer_allowed().await {
+                    self.save_group(group_id, name, kind, members);
+                } else {
+                    self.reject("SaveGroup", "operation", None, None);
+                }
+            }
+            Operation::DeleteGroup { group_id } => {
+                if self.operation_writer_allowed().await {
+                    info!("[LEADERBOARD] Delete group {}", group_id);
+                    self.state.groups.remove(&group_id).expect("Lỗi xoá nhóm");
+                } else {
+                    self.reject("DeleteGroup", "operation", None, None);
+                }
+            }
+            Operation::SetScoringFormula(formula) => {
+                if self.ensure_admin("SetScoringFormula") {
+                    self.set_scoring_formula(formula).await;
+                }
+            }
+            Operation::SetIntegrityConfig(integrity) => {
+                if self.ensure_admin("SetIntegrityConfig") {
+                    info!("[LEADERBOARD] Integrity config -> {:?}", integrity);
+                    let mut config = self.state.config.get().clone();
+                    config.integrity = integrity;
+                    self.state.config.set(config);
+                }
+            }
+            Operation::ClearFlag { match_id } => {
+                if self.ensure_admin("ClearFlag") {
+                    self.clear_flag(&match_id).await;
+                }
+            }
+            Operation::CreateBoard { board_id, name, config } => {
+                if self.ensure_admin("CreateBoard") {
+                    self.create_board(board_id, name, config).await;
+                }
+            }
+            Operation::ArchiveBoard { board_id } => {
+                if self.ensure_admin("ArchiveBoard") {
+                    self.archive_board(&board_id).await;
+                }
+            }
+            Operation::TakeSnapshot { id, n } => {
+                if self.ensure_admin("TakeSnapshot") {
+                    self.take_snapshot(id, n).await;
+                }
+            }
+            Operation::GetSnapshot { id } => {
+                let snapshot = self.state.snapshots.get(&id).await.expect("Lỗi đọc snapshot");
+                return LeaderboardResponse::Snapshot(snapshot);
+            }
+            Operation::GetPlayerStats { user_id } => {
+                let standing = self.player_standing(&user_id).await;
+                return LeaderboardResponse::Stats(standing);
+            }
+            Operation::ApplyDecay => {
+                if self.ensure_admin("ApplyDecay") {
+                    self.apply_decay_all().await;
+                }
+            }
+            Operation::AuthorizeApplication { application_id } => {
+                // Factory xfighter cấp quyền cho app con nó vừa tạo trong `OpenAndCreate`; app con thì không được
+                // cấp quyền tiếp, kể cả khi block được ký bởi admin.
+                if let Some(caller) = self.runtime.authenticated_caller_id() {
+                    if self.caller_is_factory_app(caller).await {
+                        info!("[LEADERBOARD] Factory {:?} authorizes writer application {:?}", caller, application_id);
+                        self.state.writer_apps.insert(&application_id).expect("Lỗi lưu writer app");
+                    } else {
+                        self.reject("AuthorizeApplication", "operation", None, None);
+                    }
+                } else if self.ensure_admin("AuthorizeApplication") {
+                    info!("[LEADERBOARD] Authorize writer application {:?}", application_id);
+                    self.state.writer_apps.insert(&application_id).expect("Lỗi lưu writer app");
+                    self.state.factory_apps.insert(&application_id).expect("Lỗi lưu factory app");
+                }
+            }
+            Operation::RevokeApplication { application_id } => {
+                if self.ensure_admin("RevokeApplication") {
+                    info!("[LEADERBOARD] Revoke writer application {:?}", application_id);
+                    self.state.writer_apps.remove(&application_id).expect("Lỗi xoá writer app");
+                    self.state.factory_apps.remove(&application_id).expect("Lỗi xoá factory app");
+                }
             }
         }
+        LeaderboardResponse::Ok
     }
 
     /// Xử lý message từ các chain khác.
     async fn execute_message(&mut self, message: Self::Message) {
-        let RecordScoreMessage { user_id, is_winner, match_id } = message;
-        // Logic này được gọi từ XFighter, nơi đã có logic kiểm tra trùng lặp
-        // cho cả trận đấu. Vì thế, không cần kiểm tra lại ở đây.
-	info!("[LEADERBOARD] Received Message::RecordScore user={} is_winner={} match_id={}",user_id, is_winner, match_id);
-        self.update_score_and_stats(user_id, is_winner, match_id).await;
+        let params: Parameters = self.runtime.application_parameters();
+        let origin = self.runtime.message_origin_chain_id();
+        let allowed = origin.is_some_and(|chain_id| params.authorized_chains.contains(&chain_id));
+        match message {
+            Message::RecordScore(message) => self.record_message(message.into(), allowed).await,
+            Message::RecordTypedScore(message) => self.record_message(message, allowed).await,
+            Message::MatchOutcome(outcome) => {
+                info!("[LEADERBOARD] Received Message::MatchOutcome match_id={} winner={} loser={}", outcome.match_id, outcome.winner, outcome.loser);
+                if allowed {
+                    self.record_outcome(outcome, "message").await;
+                } else {
+                    self.reject("RecordMatch", "message", Some(&outcome.match_id), None);
+                }
+            }
+        }
     }
 }
 
 impl LeaderboardContract {
-    /// Hàm xử lý logic cập nhật điểm số.
+    /// Tất cả user_id có trong các bộ đếm của mùa hiện tại (quét toàn bộ).
+    async fn user_ids(&self) -> Vec<String> {
+        let mut user_ids = self.state.scores.indices().await.unwrap_or_default().into_iter().collect::<HashSet<_>>();
+        user_ids.extend(self.state.total_wins.indices().await.unwrap_or_default());
+        user_ids.extend(self.state.total_losses.indices().await.unwrap_or_default());
+        user_ids.extend(self.state.total_matches.indices().await.unwrap_or_default());
+        user_ids.into_iter().collect()
+    }
+
+    /// Một nửa kết quả nhận qua message (dạng cũ đã được chuyển sang `TypedRecordScoreMessage`).
+    async fn record_message(&mut self, message: TypedRecordScoreMessage, allowed: bool) {
+	info!("[LEADERBOARD] Received Message::RecordScore user={} is_winner={} match_id={} match_type={}",message.user_id, message.is_winner, message.match_id, message.match_type);
+        if allowed {
+            self.record_once(message, "message").await;
+        } else {
+            self.reject("RecordScore", "message", Some(&message.match_id), Some(&message.user_id));
+        }
+    }
+
+    /// Operation ghi điểm chỉ hợp lệ khi được gọi từ ứng dụng xfighter đã cấu hình / được cho phép,
+    /// hoặc được ký bởi một owner trong `authorized_owners` / `admins`.
+    async fn operation_writer_allowed(&mut self) -> bool {
+        if self.caller_is_writer_app().await {
+            return true;
+        }
+        let params: Parameters = self.runtime.application_parameters();
+        self.runtime
+            .authenticated_signer()
+            .is_some_and(|signer| params.authorized_owners.contains(&signer) || params.admins.contains(&signer))
+    }
+
+    /// Ứng dụng đang gọi (`call_application`) là `Parameters::xfighter_app_id` hoặc nằm trong `writer_apps`.
+    async fn caller_is_writer_app(&mut self) -> bool {
+        let Some(caller) = self.runtime.authenticated_caller_id() else {
+            return false;
+        };
+        let params: Parameters = self.runtime.application_parameters();
+        params.xfighter_app_id == Some(caller)
+            || self.state.writer_apps.contains(&caller).await.expect("Lỗi kiểm tra writer app")
+    }
+
+    /// Ứng dụng được phép cấp quyền ghi điểm cho app khác: `Parameters::xfighter_app_id`, hoặc app do admin cấp
+    /// quyền trực tiếp (factory được authorize sau khi tạo). App do factory cấp quyền không nằm trong `factory_apps`.
+    async fn caller_is_factory_app(&mut self, caller: ApplicationId) -> bool {
+        let params: Parameters = self.runtime.application_parameters();
+        params.xfighter_app_id == Some(caller)
+            || self.state.factory_apps.contains(&caller).await.expect("Lỗi kiểm tra factory app")
+    }
+
+    /// Operation quản trị: signer phải nằm trong `admins`, hoặc là owner của chain khi `admins` rỗng.
+    /// Bị từ chối thì ghi vào `rejected_writes` và trả về false.
+    fn ensure_admin(&mut self, action: &str) -> bool {
+        let params: Parameters = self.runtime.application_parameters();
+        let allowed = match self.runtime.authenticated_signer() {
+            Some(signer) if params.admins.is_empty() => {
+                self.runtime.chain_ownership().all_owners().any(|owner| *owner == signer)
+            }
+            Some(signer) => params.admins.contains(&signer),
+            None => false,
+        };
+        if !allowed {
+            self.reject(action, "operation", None, None);
+        }
+        allowed
+    }
+
+    /// Ghi nhận một lần ghi / thao tác bị từ chối để có thể truy vấn lại.
+    fn reject(&mut self, action: &str, source: &str, match_id: Option<&str>, user_id: Option<&str>) {
+        let caller = format!(
+            "app={:?} signer={:?} origin_chain={:?}",
+            self.runtime.authenticated_caller_id(),
+            self.runtime.authenticated_signer(),
+            self.runtime.message_origin_chain_id(),
+        );
+        info!("[LEADERBOARD] Rejected {} ({}) from {}", action, source, caller);
+        self.state.rejected_writes.push(RejectedWrite {
+            action: action.to_string(),
+            source: source.to_string(),
+            caller,
+            match_id: match_id.map(str::to_string),
+            user_id: user_id.map(str::to_string),
+            timestamp: self.runtime.system_time().micros(),
+        });
+    }
+
+    /// Ghi cả trận trong cùng một lần thực thi: hai nửa được ghi và ghép ngay, không phải chờ nhau.
+    async fn record_outcome(&mut self, mut outcome: MatchOutcome, source: &str) {
+        if outcome.winner == outcome.loser || outcome.winner.is_empty() || outcome.loser.is_empty() {
+            info!("[LEADERBOARD] MatchOutcome không hợp lệ match_id={} winner={} loser={}, bỏ qua", outcome.match_id, outcome.winner, outcome.loser);
+            return;
+        }
+        if !self.state.match_outcomes.contains_key(&outcome.match_id).await.expect("Lỗi kiểm tra match outcome") {
+            self.state.match_outcomes.insert(&outcome.match_id, outcome.clone()).expect("Lỗi lưu match outcome");
+        }
+        let mut fresh = true;
+        for user_id in [&outcome.winner, &outcome.loser] {
+            let resolved = self.state.resolve(user_id).await;
+            fresh &= !self.is_processed(&outcome.match_id, user_id).await;
+            fresh &= !self.is_processed(&outcome.match_id, &resolved).await;
+        }
+        for half in outcome.halves() {
+            self.record_once(half, source).await;
+        }
+
+        // Hero chỉ có trong MatchOutcome; chỉ tính khi cả trận là mới.
+        if fresh {
+            outcome.winner = self.state.resolve(&outcome.winner).await;
+            outcome.loser = self.state.resolve(&outcome.loser).await;
+            let mut heroes = Vec::new();
+            let players = [(&outcome.winner, &outcome.winner_hero, true), (&outcome.loser, &outcome.loser_hero, false)];
+            for (user_id, hero, won) in players {
+                if let Some(hero) = hero.as_ref().filter(|hero| !hero.is_empty()) {
+                    self.update_hero_stats(user_id, hero, won, true).await;
+                    heroes.push((user_id.clone(), hero.clone()));
+                }
+            }
+            let board_id = match &outcome.board_id {
+                Some(board_id) if self.named_board_accepts(board_id, &outcome.match_type).await => {
+                    self.update_named_board(board_id, &outcome.winner, true, true).await;
+                    self.update_named_board(board_id, &outcome.loser, false, true).await;
+                    Some(board_id.clone())
+                }
+                _ => None,
+            };
+            if let Some(mut delta) = self.match_delta(&outcome.match_id).await {
+                delta.heroes = heroes;
+                delta.board_id = board_id;
+                self.state.match_deltas.insert(&outcome.match_id, delta).expect("Lỗi lưu match delta");
+            }
+        }
+        self.exclude_if_flagged(&outcome.match_id).await;
+    }
+
+    /// Lưu trận vào lịch sử của cặp và gắn cờ nếu có dấu hiệu boosting / win-trading.
+    /// Trận đã có trong hàng đợi review (kể cả đã được admin xác nhận) không bị xét lại.
+    async fn check_integrity(&mut self, winner: &str, loser: &str, match_id: &str, match_type: &str, map_name: &str) {
+        let now = self.runtime.system_time().micros();
+        let key = head_to_head_key(winner, loser);
+        let mut recent = self.state.pair_matches.get(&key).await.ok().flatten().unwrap_or_default();
+        if !recent.iter().any(|m| m.match_id == match_id) {
+            recent.push(PairMatch { match_id: match_id.to_string(), winner: winner.to_string(), timestamp: now });
+            if recent.len() > PAIR_HISTORY_LEN {
+                recent.remove(0);
+            }
+            self.state.pair_matches.insert(&key, recent.clone()).expect("Lỗi lưu lịch sử cặp đấu");
+        }
+        if self.state.flagged_matches.contains_key(match_id).await.expect("Lỗi kiểm tra trận bị gắn cờ") {
+            return;
+        }
+
+        let duration = self
+            .state
+            .match_outcomes
+            .get(match_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|outcome| outcome.duration_seconds)
+            .unwrap_or_default();
+        let reasons = self.state.config.get().integrity.check(&recent, now, duration);
+        if reasons.is_empty() {
+            return;
+        }
+        info!("[LEADERBOARD] Gắn cờ match_id={} ({} vs {}): {:?}", match_id, winner, loser, reasons);
+        let flag = FlaggedMatch {
+            match_id: match_id.to_string(),
+            winner: winner.to_string(),
+            loser: loser.to_string(),
+            match_type: match_type.to_string(),
+            map_name: map_name.to_string(),
+            reasons,
+            flagged_at: now,
+            excluded: false,
+            cleared: false,
+            cleared_by: None,
+            cleared_at: None,
+        };
+        self.state.flagged_matches.insert(match_id, flag).expect("Lỗi lưu trận bị gắn cờ");
+    }
+
+    /// Hoàn tác trận bị gắn cờ khỏi bảng xếp hạng khi `IntegrityConfig::exclude_flagged` bật.
+    /// Khoá idempotency vẫn giữ nên gửi lại cũng không ghi lại được; chỉ `ClearFlag` mới ghi lại.
+    async fn exclude_if_flagged(&mut self, match_id: &str) {
+        if !self.state.config.get().integrity.exclude_flagged {
+            return;
+        }
+        let Some(mut flag) = self.state.flagged_matches.get(match_id).await.ok().flatten() else {
+            return;
+        };
+        if flag.cleared || flag.excluded {
+            return;
+        }
+        let previous = self.void_match(match_id).await;
+        self.log_correction(match_id, "exclude", previous.as_ref(), None);
+        flag.excluded = true;
+        self.state.flagged_matches.insert(match_id, flag).expect("Lỗi lưu trận bị gắn cờ");
+    }
+
+    /// Admin xác nhận trận bị gắn cờ; nếu trận đang bị loại thì ghi lại như lần đầu.
+    async fn clear_flag(&mut self, match_id: &str) {
+        let Some(mut flag) = self.state.flagged_matches.get(match_id).await.ok().flatten() else {
+            info!("[LEADERBOARD] ClearFlag: match_id={} không bị gắn cờ", match_id);
+            return;
+        };
+        if flag.cleared {
+            return;
+        }
+        let excluded = flag.excluded;
+        flag.cleared = true;
+        flag.excluded = false;
+        flag.cleared_by = Some(format!("{:?}", self.runtime.authenticated_signer()));
+        flag.cleared_at = Some(self.runtime.system_time().micros());
+        self.state.flagged_matches.insert(match_id, flag.clone()).expect("Lỗi lưu trận bị gắn cờ");
+        if !excluded {
+            return;
+        }
+
+        let winner = self.state.resolve(&flag.winner).await;
+        let loser = self.state.resolve(&flag.loser).await;
+        for user_id in [&winner, &loser, &flag.winner, &flag.loser] {
+            self.state.processed_match_ids.remove(&processed_key(match_id, user_id)).expect("Lỗi xoá match_id");
+        }
+        match self.state.match_outcomes.get(match_id).await.ok().flatten() {
+            Some(outcome) => self.record_outcome(outcome, "review").await,
+            None => {
+                for (user_id, is_winner) in [(&winner, true), (&loser, false)] {
+                    let result = TypedRecordScoreMessage {
+                        user_id: user_id.clone(),
+                        is_winner,
+                        match_id: match_id.to_string(),
+                        match_type: flag.match_type.clone(),
+                        map_name: flag.map_name.clone(),
+                    };
+                    self.record_once(result, "review").await;
+                }
+            }
+        }
+        self.log_correction(match_id, "restore", None, Some((winner.as_str(), loser.as_str())));
+    }
+
+    /// Tạo bảng có tên; không ghi đè bảng đã tồn tại.
+    async fn create_board(&mut self, board_id: String, name: String, config: BoardConfig) {
+        if self.state.board_registry.contains_key(&board_id).await.expect("Lỗi kiểm tra bảng") {
+            info!("[LEADERBOARD] Bảng {} đã tồn tại", board_id);
+            return;
+        }
+        info!("[LEADERBOARD] Tạo bảng {} ({})", board_id, name);
+        let board = BoardInfo {
+            board_id: board_id.clone(),
+            name,
+            config,
+            archived: false,
+            created_at: self.runtime.system_time().micros(),
+        };
+        self.state.board_registry.insert(&board_id, board).expect("Lỗi lưu bảng");
+    }
+
+    async fn archive_board(&mut self, board_id: &str) {
+        let Some(mut board) = self.state.board_registry.get(board_id).await.ok().flatten() else {
+            info!("[LEADERBOARD] Không có bảng {}", board_id);
+            return;
+        };
+        info!("[LEADERBOARD] Lưu trữ bảng {}", board_id);
+        board.archived = true;
+        self.state.board_registry.insert(board_id, board).expect("Lỗi lưu bảng");
+    }
+
+    /// Bảng có tên tồn tại, chưa lưu trữ và tính loại trận này.
+    async fn named_board_accepts(&self, board_id: &str, match_type: &str) -> bool {
+        match self.state.board_registry.get(board_id).await.ok().flatten() {
+            Some(board) if !board.archived => board.config.counts(match_type),
+            Some(_) => {
+                info!("[LEADERBOARD] Bảng {} đã lưu trữ, bỏ qua", board_id);
+                false
+            }
+            None => {
+                info!("[LEADERBOARD] Không có bảng {}, bỏ qua", board_id);
+                false
+            }
+        }
+    }
+
+    /// Cộng / trừ một kết quả vào bảng có tên, tính điểm theo cấu hình của bảng.
+    async fn update_named_board(&mut self, board_id: &str, user_id: &str, won: bool, add: bool) {
+        let Some(info) = self.state.board_registry.get(board_id).await.ok().flatten() else {
+            return;
+        };
+        let board = self.state.named_boards.load_entry_mut(board_id).await.expect("Lỗi tải bảng");
+        let mut stats = board.stats.get(user_id).await.ok().flatten().unwrap_or_default();
+        board.score_index.remove(&RankKey::new(stats.score, user_id)).expect("Lỗi xoá chỉ mục bảng");
+
+        let step = |value: u64| if add { value + 1 } else { value.saturating_sub(1) };
+        if won {
+            stats.wins = step(stats.wins);
+        } else {
+            stats.losses = step(stats.losses);
+        }
+        stats.matches = step(stats.matches);
+        stats.score = info.config.score(stats.wins, stats.losses);
+
+        board.score_index.insert(&RankKey::new(stats.score, user_id), ()).expect("Lỗi lưu chỉ mục bảng");
+        board.stats.insert(user_id, stats).expect("Lỗi lưu bảng");
+    }
+
+    /// Cộng / trừ thắng-thua của người chơi trên một map.
+    async fn update_map_stats(&mut self, user_id: &str, map_name: &str, won: bool, add: bool) {
+        if map_name.is_empty() {
+            return;
+        }
+        let mut breakdown = self.state.player_breakdowns.get(user_id).await.ok().flatten().unwrap_or_default();
+        breakdown.maps.entry(map_name.to_string()).or_default().apply(won, add);
+        self.state.player_breakdowns.insert(user_id, breakdown).expect("Lỗi lưu thống kê map");
+    }
+
+    /// Cộng / trừ thắng-thua của người chơi với một hero, và lượt chọn hero toàn cục.
+    async fn update_hero_stats(&mut self, user_id: &str, hero: &str, won: bool, add: bool) {
+        let mut breakdown = self.state.player_breakdowns.get(user_id).await.ok().flatten().unwrap_or_default();
+        breakdown.heroes.entry(hero.to_string()).or_default().apply(won, add);
+        self.state.player_breakdowns.insert(user_id, breakdown).expect("Lỗi lưu thống kê hero");
+
+        let mut usage = self.state.hero_usage.get(hero).await.ok().flatten().unwrap_or_default();
+        usage.apply(won, add);
+        self.state.hero_usage.insert(hero, usage).expect("Lỗi lưu lượt chọn hero");
+    }
+
+    /// Cộng / trừ một trận vào lượt chọn map toàn cục.
+    async fn update_map_usage(&mut self, map_name: &str, add: bool) {
+        let matches = *self.state.usage_matches.get();
+        self.state.usage_matches.set(if add { matches + 1 } else { matches.saturating_sub(1) });
+        if map_name.is_empty() {
+            return;
+        }
+        let picks = self.state.map_usage.get(map_name).await.ok().flatten().unwrap_or_default();
+        let picks = if add { picks + 1 } else { picks.saturating_sub(1) };
+        self.state.map_usage.insert(map_name, picks).expect("Lỗi lưu lượt chọn map");
+    }
+
+    /// Ghi kết quả nếu cặp (match_id, user_id) chưa từng được xử lý.
+    /// Message bị gửi lại hoặc mutation `recordScore` bị gọi hai lần chỉ được ghi vào `duplicate_results`.
+    async fn record_once(&mut self, mut result: TypedRecordScoreMessage, source: &str) {
+        // Kết quả cũ có thể mang tên đã được gộp / đổi; kiểm tra trùng theo cả tên cũ và tên hiện tại.
+        let raw_processed = self.is_processed(&result.match_id, &result.user_id).await;
+        result.user_id = self.state.resolve(&result.user_id).await;
+        let key = processed_key(&result.match_id, &result.user_id);
+        if raw_processed || self.is_processed(&result.match_id, &result.user_id).await {
+            info!("[LEADERBOARD] Duplicate result match_id={} user={} ({}), skipping.", result.match_id, result.user_id, source);
+            self.state.duplicate_results.push(DuplicateResult {
+                match_id: result.match_id,
+                user_id: result.user_id,
+                is_winner: result.is_winner,
+                source: source.to_string(),
+            });
+            return;
+        }
+        self.state.processed_match_ids.insert(&key, true).expect("Lỗi lưu match_id");
+
+        self.update_boards(&result).await;
+        self.update_map_stats(&result.user_id, &result.map_name, result.is_winner, true).await;
+        let headline = self.state.config.get().counts_for_headline(&result.match_type);
+        if headline {
+            self.update_score_and_stats(&result).await;
+            self.refresh_provisional(&result.user_id).await;
+            self.record_history(&result.user_id, &result.match_id).await;
+        } else {
+            info!("[LEADERBOARD] match_type={} không tính vào bảng chính (match_id={})", result.match_type, result.match_id);
+        }
+
+        let mut delta = self.match_delta(&result.match_id).await.unwrap_or_else(|| MatchDelta {
+            season_id: self.state.season_id(),
+            match_type: result.match_type.clone(),
+            map_name: result.map_name.clone(),
+            ..MatchDelta::default()
+        });
+        delta.players.push(PlayerDelta {
+            user_id: result.user_id.clone(),
+            is_winner: result.is_winner,
+            headline,
+            rating_delta: 0,
+        });
+        self.state.match_deltas.insert(&result.match_id, delta).expect("Lỗi lưu match delta");
+
+        let match_id = result.match_id.clone();
+        self.pair_results(result, headline).await;
+        // Trận đến qua MatchOutcome được xử lý ở cuối `record_outcome`, khi delta đã đầy đủ.
+        if !self.state.match_outcomes.contains_key(&match_id).await.expect("Lỗi kiểm tra match outcome") {
+            self.exclude_if_flagged(&match_id).await;
+        }
+    }
+
+    async fn is_processed(&self, match_id: &str, user_id: &str) -> bool {
+        let key = processed_key(match_id, user_id);
+        self.state.processed_match_ids.contains_key(&key).await.expect("Lỗi kiểm tra match_id")
+    }
+
+    async fn match_delta(&self, match_id: &str) -> Option<MatchDelta> {
+        self.state.match_deltas.get(match_id).await.ok().flatten()
+    }
+
+    /// Hoàn tác mọi thay đổi của một trận. Trả về các thay đổi đã hoàn tác (None nếu trận chưa được ghi).
+    async fn void_match(&mut self, match_id: &str) -> Option<MatchDelta> {
+        let Some(mut delta) = self.match_delta(match_id).await else {
+            info!("[LEADERBOARD] VoidMatch: không có dữ liệu cho match_id={}", match_id);
+            return None;
+        };
+        // Người chơi có thể đã được gộp / đổi tên sau trận.
+        for player in delta.players.iter_mut() {
+            player.user_id = self.state.resolve(&player.user_id).await;
+        }
+        for (user_id, _) in delta.heroes.iter_mut() {
+            *user_id = self.state.resolve(user_id).await;
+        }
+        let same_season = delta.season_id == self.state.season_id();
+
+        for player in &delta.players {
+            if same_season {
+                self.revert_boards(player, &delta).await;
+                if player.headline {
+                    self.revert_score_and_stats(player).await;
+                    self.rebuild_streak(&player.user_id, match_id).await;
+                }
+            }
+            self.update_map_stats(&player.user_id, &delta.map_name, player.is_winner, false).await;
+            if let Some(board_id) = &delta.board_id {
+                self.update_named_board(board_id, &player.user_id, player.is_winner, false).await;
+            }
+            if let Some((_, hero)) = delta.heroes.iter().find(|(user_id, _)| user_id == &player.user_id) {
+                self.update_hero_stats(&player.user_id, hero, player.is_winner, false).await;
+            }
+            if player.rating_delta != 0 {
+                let rating = self.state.ratings.get(&player.user_id).await.ok().flatten().unwrap_or(INITIAL_RATING);
+                let reverted = (rating as i64 - player.rating_delta).max(rating::RATING_FLOOR as i64) as u64;
+                self.set_rating(&player.user_id, reverted).await;
+                let rated = self.state.rated_matches.get(&player.user_id).await.ok().flatten().unwrap_or_default();
+                self.state.rated_matches.insert(&player.user_id, rated.saturating_sub(1)).expect("Lỗi lưu số trận rating");
+                self.refresh_provisional(&player.user_id).await;
+            }
+        }
+        if delta.head_to_head {
+            if let Some((winner, loser)) = delta.pair() {
+                self.revert_head_to_head(winner, loser).await;
+            }
+            self.update_map_usage(&delta.map_name, false).await;
+        }
+
+        info!("[LEADERBOARD] Voided match_id={} ({} người chơi)", match_id, delta.players.len());
+        self.remove_pending(match_id).await;
+        self.state.match_deltas.remove(match_id).expect("Lỗi xoá match delta");
+        Some(delta)
+    }
+
+    /// Huỷ trận rồi ghi lại với kết quả đúng (giữ loại trận / map của lần ghi cũ).
+    async fn correct_match(&mut self, match_id: &str, winner: &str, loser: &str) -> Option<MatchDelta> {
+        let winner = &self.state.resolve(winner).await;
+        let loser = &self.state.resolve(loser).await;
+        let previous = self.void_match(match_id).await;
+        let (match_type, map_name) = previous
+            .as_ref()
+            .map(|delta| (delta.match_type.clone(), delta.map_name.clone()))
+            .unwrap_or_default();
+
+        let mut users = vec![winner.to_string(), loser.to_string()];
+        users.extend(previous.iter().flat_map(|delta| delta.players.iter().map(|p| p.user_id.clone())));
+        for user_id in users {
+            self.state.processed_match_ids.remove(&processed_key(match_id, &user_id)).expect("Lỗi xoá match_id");
+        }
+
+        for (user_id, is_winner) in [(winner, true), (loser, false)] {
+            let result = TypedRecordScoreMessage {
+                user_id: user_id.to_string(),
+                is_winner,
+                match_id: match_id.to_string(),
+                match_type: match_type.clone(),
+                map_name: map_name.clone(),
+            };
+            self.record_once(result, "correction").await;
+        }
+        previous
+    }
+
+    /// Ghi nhật ký huỷ / sửa trận.
+    fn log_correction(&mut self, match_id: &str, action: &str, previous: Option<&MatchDelta>, corrected: Option<(&str, &str)>) {
+        let previous_pair = previous.and_then(|delta| delta.pair());
+        self.state.corrections.push(MatchCorrection {
+            match_id: match_id.to_string(),
+            action: action.to_string(),
+            previous_winner: previous_pair.map(|(winner, _)| winner.to_string()),
+            previous_loser: previous_pair.map(|(_, loser)| loser.to_string()),
+            new_winner: corrected.map(|(winner, _)| winner.to_string()),
+            new_loser: corrected.map(|(_, loser)| loser.to_string()),
+            admin: format!("{:?}", self.runtime.authenticated_signer()),
+            timestamp: self.runtime.system_time().micros(),
+        });
+    }
+
+    /// Trừ lại bộ đếm bảng phụ mà một người chơi đã nhận từ trận.
+    async fn revert_boards(&mut self, player: &PlayerDelta, delta: &MatchDelta) {
+        for board_key in leaderboard::board_keys(&delta.match_type, &delta.map_name) {
+            let board = self.state.boards.load_entry_mut(&board_key).await.expect("Lỗi tải bảng phụ");
+            let Some(mut stats) = board.stats.get(&player.user_id).await.ok().flatten() else {
+                continue;
+            };
+            board.score_index.remove(&RankKey::new(stats.score, &player.user_id)).expect("Lỗi xoá chỉ mục bảng phụ");
+
+            if player.is_winner {
+                stats.wins = stats.wins.saturating_sub(1);
+            } else {
+                stats.losses = stats.losses.saturating_sub(1);
+            }
+            stats.matches = stats.matches.saturating_sub(1);
+            stats.score = stats.wins_score();
+
+            board.score_index.insert(&RankKey::new(stats.score, &player.user_id), ()).expect("Lỗi lưu chỉ mục bảng phụ");
+            board.stats.insert(&player.user_id, stats).expect("Lỗi lưu bảng phụ");
+        }
+    }
+
+    /// Trừ lại bộ đếm bảng chính mà một người chơi đã nhận từ trận.
+    async fn revert_score_and_stats(&mut self, player: &PlayerDelta) {
+        let user_id = &player.user_id;
+        let mut current_wins = self.state.total_wins.get(user_id).await.ok().flatten().unwrap_or_default();
+        let mut current_losses = self.state.total_losses.get(user_id).await.ok().flatten().unwrap_or_default();
+        let current_matches = self.state.total_matches.get(user_id).await.ok().flatten().unwrap_or_default();
+
+        if player.is_winner {
+            current_wins = current_wins.saturating_sub(1);
+        } else {
+            current_losses = current_losses.saturating_sub(1);
+        }
+
+        self.state.total_wins.insert(user_id, current_wins).expect("Lỗi lưu wins");
+        self.state.total_losses.insert(user_id, current_losses).expect("Lỗi lưu losses");
+        self.state.total_matches.insert(user_id, current_matches.saturating_sub(1)).expect("Lỗi lưu matches");
+        self.refresh_score(user_id).await;
+    }
+
+    /// Tính lại chuỗi / phong độ từ lịch sử kết quả, bỏ trận `match_id`.
+    /// Trận cũ hơn `RESULT_HISTORY_LEN` kết quả gần nhất đã được gộp vào chuỗi nên chuỗi giữ nguyên.
+    async fn rebuild_streak(&mut self, user_id: &str, match_id: &str) {
+        let mut history = self.state.player_results.get(user_id).await.ok().flatten().unwrap_or_default();
+        if !history.remove(match_id) {
+            info!("[LEADERBOARD] match_id={} không còn trong lịch sử kết quả của {}, giữ nguyên chuỗi", match_id, user_id);
+            return;
+        }
+        self.state.streaks.insert(user_id, history.streak()).expect("Lỗi lưu streak");
+        self.state.player_results.insert(user_id, history).expect("Lỗi lưu lịch sử kết quả");
+    }
+
+    /// Trừ lại một trận thắng trong thành tích đối đầu.
+    async fn revert_head_to_head(&mut self, winner: &str, loser: &str) {
+        let key = head_to_head_key(winner, loser);
+        let Some(mut record) = self.state.head_to_head.get(&key).await.ok().flatten() else {
+            return;
+        };
+        if key.0 == winner {
+            record.wins_a = record.wins_a.saturating_sub(1);
+        } else {
+            record.wins_b = record.wins_b.saturating_sub(1);
+        }
+        self.state.head_to_head.insert(&key, record).expect("Lỗi lưu đối đầu");
+    }
+
+    /// Cập nhật các bảng phụ theo loại trận / map của kết quả.
+    async fn update_boards(&mut self, result: &TypedRecordScoreMessage) {
+        for board_key in leaderboard::board_keys(&result.match_type, &result.map_name) {
+            let board = self.state.boards.load_entry_mut(&board_key).await.expect("Lỗi tải bảng phụ");
+            let mut stats = board.stats.get(&result.user_id).await.ok().flatten().unwrap_or_default();
+            board.score_index.remove(&RankKey::new(stats.score, &result.user_id)).expect("Lỗi xoá chỉ mục bảng phụ");
+
+            if result.is_winner {
+                stats.wins += 1;
+            } else {
+                stats.losses += 1;
+            }
+            stats.matches += 1;
+            stats.score = stats.wins_score();
+
+            board.score_index.insert(&RankKey::new(stats.score, &result.user_id), ()).expect("Lỗi lưu chỉ mục bảng phụ");
+            board.stats.insert(&result.user_id, stats).expect("Lỗi lưu bảng phụ");
+        }
+    }
+
+    /// Hàm xử lý logic cập nhật điểm số của bảng chính.
     /// Dùng chung cho cả Operation và Message.
-    async fn update_score_and_stats(&mut self, user_id: String, is_winner: bool, match_id: String) {
+    async fn update_score_and_stats(&mut self, result: &TypedRecordScoreMessage) {
+        let user_id = result.user_id.clone();
+        let is_winner = result.is_winner;
         let mut current_wins = self.state.total_wins.get(&user_id).await.ok().flatten().unwrap_or_default();
         let mut current_losses = self.state.total_losses.get(&user_id).await.ok().flatten().unwrap_or_default();
         let mut current_matches = self.state.total_matches.get(&user_id).await.ok().flatten().unwrap_or_default();
@@ -86,14 +834,769 @@ impl LeaderboardContract {
         }
         current_matches += 1;
 
-        // Công thức mới: Điểm = Số trận thắng (Win = +1, Lose = 0)
-        let new_score = current_wins;
-        
         // Lưu các giá trị đã cập nhật
         self.state.total_wins.insert(&user_id, current_wins).expect("Lỗi lưu wins");
         self.state.total_losses.insert(&user_id, current_losses).expect("Lỗi lưu losses");
         self.state.total_matches.insert(&user_id, current_matches).expect("Lỗi lưu matches");
-        self.state.scores.insert(&user_id, new_score).expect("Lỗi lưu score");
-        self.state.processed_match_ids.insert(&match_id, true).expect("Lỗi lưu match_id");
+
+        let mut streak = self.state.streaks.get(&user_id).await.ok().flatten().unwrap_or_default();
+        streak.record(is_winner);
+        self.state.streaks.insert(&user_id, streak).expect("Lỗi lưu streak");
+
+        let mut history = self.state.player_results.get(&user_id).await.ok().flatten().unwrap_or_default();
+        let timestamp = self.runtime.system_time().micros();
+        history.push(PlayerResult { match_id: result.match_id.clone(), won: is_winner, timestamp });
+        self.state.player_results.insert(&user_id, history).expect("Lỗi lưu lịch sử kết quả");
+
+        if !self.state.ratings.contains_key(&user_id).await.expect("Lỗi kiểm tra rating") {
+            self.set_rating(&user_id, INITIAL_RATING).await;
+        }
+        self.refresh_score(&user_id).await;
+    }
+
+    /// Gộp mọi dữ liệu của `from` vào `to`, rồi ghi alias `from` -> `to` để kết quả cũ / gửi lại vẫn trỏ về `to`.
+    async fn merge_players(&mut self, from: &str, to: &str, action: &str) {
+        let from = self.state.resolve(from).await;
+        let to = self.state.resolve(to).await;
+        if from == to || from.is_empty() || to.is_empty() {
+            info!("[LEADERBOARD] {} bỏ qua: {} -> {}", action, from, to);
+            return;
+        }
+        let (from, to) = (from.as_str(), to.as_str());
+        info!("[LEADERBOARD] {} player {} -> {}", action, from, to);
+
+        // Bộ đếm mùa hiện tại.
+        merge_counter(&mut self.state.total_wins, from, to).await;
+        merge_counter(&mut self.state.total_losses, from, to).await;
+        merge_counter(&mut self.state.total_matches, from, to).await;
+        if let Some(old) = self.state.scores.get(from).await.ok().flatten() {
+            self.state.score_index.remove(&RankKey::new(old, from)).expect("Lỗi xoá chỉ mục score");
+            self.state.scores.remove(from).expect("Lỗi xoá score");
+        }
+
+        // Rating / hạng / decay: giữ của `to` nếu đã có.
+        if let Some(rating) = self.state.ratings.get(from).await.ok().flatten() {
+            self.state.rating_index.remove(&RankKey::new(rating, from)).expect("Lỗi xoá chỉ mục rating");
+            self.state.ratings.remove(from).expect("Lỗi xoá rating");
+            if !self.state.ratings.contains_key(to).await.expect("Lỗi kiểm tra rating") {
+                self.set_rating(to, rating).await;
+            }
+        }
+        if let Some(tier) = self.state.tiers.get(from).await.ok().flatten() {
+            if !self.state.tiers.contains_key(to).await.expect("Lỗi kiểm tra tier") {
+                self.state.tiers.insert(to, tier).expect("Lỗi lưu tier");
+            }
+            self.state.tiers.remove(from).expect("Lỗi xoá tier");
+        }
+        if let Some(last) = self.state.last_played.get(from).await.ok().flatten() {
+            let to_last = self.state.last_played.get(to).await.ok().flatten().unwrap_or_default();
+            self.state.last_played.insert(to, last.max(to_last)).expect("Lỗi lưu last_played");
+            self.state.last_played.remove(from).expect("Lỗi xoá last_played");
+            self.state.decayed_until.remove(from).expect("Lỗi xoá decayed_until");
+        }
+        if self.state.total_matches.contains_key(to).await.expect("Lỗi kiểm tra số trận") {
+            self.refresh_score(to).await;
+        }
+
+        // Chuỗi / phong độ: gộp lịch sử kết quả theo thời gian rồi tính lại.
+        if let Some(from_history) = self.state.player_results.get(from).await.ok().flatten() {
+            let to_history = self.state.player_results.get(to).await.ok().flatten().unwrap_or_default();
+            let history = to_history.merge(from_history);
+            self.state.streaks.insert(to, history.streak()).expect("Lỗi lưu streak");
+            self.state.player_results.insert(to, history).expect("Lỗi lưu lịch sử kết quả");
+        }
+        self.state.player_results.remove(from).expect("Lỗi xoá lịch sử kết quả");
+        self.state.streaks.remove(from).expect("Lỗi xoá streak");
+
+        self.merge_head_to_head(from, to).await;
+        self.merge_boards(from, to).await;
+
+        // Thống kê map / hero, lịch sử mùa.
+        if let Some(from_breakdown) = self.state.player_breakdowns.get(from).await.ok().flatten() {
+            let mut breakdown = self.state.player_breakdowns.get(to).await.ok().flatten().unwrap_or_default();
+            for (map_name, counter) in from_breakdown.maps {
+                let target = breakdown.maps.entry(map_name).or_default();
+                target.wins += counter.wins;
+                target.losses += counter.losses;
+            }
+            for (hero, counter) in from_breakdown.heroes {
+                let target = breakdown.heroes.entry(hero).or_default();
+                target.wins += counter.wins;
+                target.losses += counter.losses;
+            }
+            self.state.player_breakdowns.insert(to, breakdown).expect("Lỗi lưu thống kê map / hero");
+            self.state.player_breakdowns.remove(from).expect("Lỗi xoá thống kê map / hero");
+        }
+        if let Some(mut seasons) = self.state.player_seasons.get(from).await.ok().flatten() {
+            seasons.extend(self.state.player_seasons.get(to).await.ok().flatten().unwrap_or_default());
+            seasons.sort_by_key(|record| record.season_id);
+            self.state.player_seasons.insert(to, seasons).expect("Lỗi lưu lịch sử mùa");
+            self.state.player_seasons.remove(from).expect("Lỗi xoá lịch sử mùa");
+        }
+
+        merge_counter(&mut self.state.rated_matches, from, to).await;
+        self.state.provisional.remove(&from.to_string()).expect("Lỗi xoá provisional");
+        if self.state.is_known(to).await {
+            self.refresh_provisional(to).await;
+        }
+
+        if let Some(mut history) = self.state.score_history.get(from).await.ok().flatten() {
+            history.extend(self.state.score_history.get(to).await.ok().flatten().unwrap_or_default());
+            history.sort_by_key(|point| point.timestamp);
+            let mut merged = Vec::with_capacity(history.len());
+            for point in history {
+                leaderboard::push_score_point(&mut merged, point);
+            }
+            self.state.score_history.insert(to, merged).expect("Lỗi lưu lịch sử score");
+            self.state.score_history.remove(from).expect("Lỗi xoá lịch sử score");
+        }
+
+        // Nửa kết quả đang chờ ghép.
+        let from_pending = self.state.pending_by_user.get(from).await.ok().flatten().unwrap_or_default();
+        self.state.pending_by_user.remove(from).expect("Lỗi xoá chỉ mục pending result");
+        for match_id in from_pending {
+            if let Some(mut pending) = self.state.pending_results.get(&match_id).await.ok().flatten() {
+                pending.user_id = to.to_string();
+                self.add_pending(pending).await;
+            }
+        }
+
+        let alias = PlayerAlias {
+            alias: from.to_string(),
+            user_id: to.to_string(),
+            action: action.to_string(),
+            timestamp: self.runtime.system_time().micros(),
+        };
+        self.state.aliases.insert(from, alias).expect("Lỗi lưu alias");
+    }
+
+    /// Lưu nhóm người chơi; thành viên trùng / rỗng bị bỏ, quá `MAX_GROUP_MEMBERS` thì cắt bớt.
+    fn save_group(&mut self, group_id: String, name: String, kind: String, members: Vec<String>) {
+        if group_id.is_empty() {
+            info!("[LEADERBOARD] SaveGroup bỏ qua: group_id rỗng");
+            return;
+        }
+        let mut unique = Vec::new();
+        for member in members {
+            if !member.is_empty() && !unique.contains(&member) {
+                unique.push(member);
+            }
+        }
+        unique.truncate(leaderboard::MAX_GROUP_MEMBERS);
+        info!("[LEADERBOARD] Save group {} ({} thành viên)", group_id, unique.len());
+        let group = PlayerGroup {
+            group_id: group_id.clone(),
+            name,
+            kind,
+            members: unique,
+            updated_at: self.runtime.system_time().micros(),
+        };
+        self.state.groups.insert(&group_id, group).expect("Lỗi lưu nhóm");
+    }
+
+    /// Chuyển thành tích đối đầu của `from` sang `to`; trận giữa hai người bị bỏ khỏi đối đầu.
+    async fn merge_head_to_head(&mut self, from: &str, to: &str) {
+        let opponents = self.state.opponents.get(from).await.ok().flatten().unwrap_or_default();
+        self.state.opponents.remove(from).expect("Lỗi xoá danh sách đối thủ");
+
+        for opponent in opponents {
+            let old_key = head_to_head_key(from, &opponent);
+            let old = self.state.head_to_head.get(&old_key).await.ok().flatten().unwrap_or_default();
+            self.state.head_to_head.remove(&old_key).expect("Lỗi xoá đối đầu");
+
+            let mut opponent_list = self.state.opponents.get(&opponent).await.ok().flatten().unwrap_or_default();
+            opponent_list.retain(|o| o != from);
+            if opponent != to && !opponent_list.iter().any(|o| o == to) {
+                opponent_list.push(to.to_string());
+            }
+            self.state.opponents.insert(&opponent, opponent_list).expect("Lỗi lưu danh sách đối thủ");
+            if opponent == to {
+                continue;
+            }
+
+            let (from_wins, opponent_wins) =
+                if old_key.0 == from { (old.wins_a, old.wins_b) } else { (old.wins_b, old.wins_a) };
+            let new_key = head_to_head_key(to, &opponent);
+            let mut record = self.state.head_to_head.get(&new_key).await.ok().flatten().unwrap_or_default();
+            if new_key.0 == to {
+                record.wins_a += from_wins;
+                record.wins_b += opponent_wins;
+            } else {
+                record.wins_a += opponent_wins;
+                record.wins_b += from_wins;
+            }
+            if record.last_match_id.is_empty() {
+                record.last_match_id = old.last_match_id;
+            }
+            self.state.head_to_head.insert(&new_key, record).expect("Lỗi lưu đối đầu");
+
+            let mut to_opponents = self.state.opponents.get(to).await.ok().flatten().unwrap_or_default();
+            if !to_opponents.iter().any(|o| o == &opponent) {
+                to_opponents.push(opponent.clone());
+                self.state.opponents.insert(to, to_opponents).expect("Lỗi lưu danh sách đối thủ");
+            }
+        }
+    }
+
+    /// Chuyển bộ đếm của `from` sang `to` trong mọi bảng phụ và bảng có tên.
+    async fn merge_boards(&mut self, from: &str, to: &str) {
+        for board_key in self.state.boards.indices().await.expect("Lỗi đọc bảng phụ") {
+            let board = self.state.boards.load_entry_mut(&board_key).await.expect("Lỗi tải bảng phụ");
+            merge_board_stats(board, from, to, BoardStats::wins_score).await;
+        }
+        for board_id in self.state.named_boards.indices().await.expect("Lỗi đọc bảng có tên") {
+            let Some(info) = self.state.board_registry.get(&board_id).await.ok().flatten() else {
+                continue;
+            };
+            let board = self.state.named_boards.load_entry_mut(&board_id).await.expect("Lỗi tải bảng");
+            merge_board_stats(board, from, to, |stats| info.config.score(stats.wins, stats.losses)).await;
+        }
+    }
+
+    /// Ghi score / rating hiện tại của người chơi vào lịch sử, gắn với `match_id`.
+    async fn record_history(&mut self, user_id: &str, match_id: &str) {
+        let point = ScorePoint {
+            timestamp: self.runtime.system_time().micros(),
+            score: self.state.scores.get(user_id).await.ok().flatten().unwrap_or_default(),
+            rating: self.state.ratings.get(user_id).await.ok().flatten().unwrap_or(INITIAL_RATING),
+            match_id: match_id.to_string(),
+        };
+        let mut history = self.state.score_history.get(user_id).await.ok().flatten().unwrap_or_default();
+        leaderboard::push_score_point(&mut history, point);
+        self.state.score_history.insert(user_id, history).expect("Lỗi lưu lịch sử score");
+    }
+
+    /// Tính lại score của một người chơi theo công thức hiện tại.
+    async fn refresh_score(&mut self, user_id: &str) {
+        let wins = self.state.total_wins.get(user_id).await.ok().flatten().unwrap_or_default();
+        let losses = self.state.total_losses.get(user_id).await.ok().flatten().unwrap_or_default();
+        let rating = self.state.ratings.get(user_id).await.ok().flatten().unwrap_or(INITIAL_RATING);
+        // xfighter chưa gửi kết quả hoà nên số trận hoà luôn là 0.
+        let score = self.state.config.get().scoring.score(wins, losses, 0, rating);
+        self.set_score(user_id, score).await;
+    }
+
+    /// Đổi công thức và tính lại score của mọi người chơi trong mùa hiện tại.
+    /// Bảng phụ theo loại trận / map không bị ảnh hưởng: chúng luôn xếp theo số trận thắng.
+    async fn set_scoring_formula(&mut self, formula: ScoringFormula) {
+        info!("[LEADERBOARD] Scoring formula -> {:?}", formula);
+        let mut config = self.state.config.get().clone();
+        config.scoring = formula;
+        self.state.config.set(config);
+        for user_id in self.user_ids().await {
+            self.refresh_score(&user_id).await;
+        }
+    }
+
+    /// Ghép hai nửa kết quả (thắng/thua) của cùng một match_id.
+    /// xfighter gửi người thắng và người thua thành hai message riêng, nên nửa đến trước
+    /// được giữ lại trong `pending_results` cho tới khi nửa còn lại tới.
+    /// Khi đủ cặp: cập nhật đối đầu, và rating nếu loại trận được tính vào bảng chính.
+    async fn pair_results(&mut self, result: TypedRecordScoreMessage, headline: bool) {
+        let match_id = result.match_id.clone();
+        let pending = self.state.pending_results.get(&match_id).await.ok().flatten();
+        match pending {
+            Some(other) if other.is_winner != result.is_winner && other.user_id != result.user_id => {
+                let (winner, loser) = if result.is_winner {
+                    (result.user_id, other.user_id)
+                } else {
+                    (other.user_id, result.user_id)
+                };
+                let rating_deltas = if headline {
+                    Some(self.update_ratings(&winner, &loser, &match_id).await)
+                } else {
+                    None
+                };
+                if headline {
+                    // Rating vừa đổi: cập nhật lại điểm lịch sử của trận này.
+                    self.record_history(&winner, &match_id).await;
+                    self.record_history(&loser, &match_id).await;
+                }
+                self.record_head_to_head(&winner, &loser, &match_id).await;
+                self.check_integrity(&winner, &loser, &match_id, &result.match_type, &result.map_name).await;
+                self.update_map_usage(&result.map_name, true).await;
+                self.remove_pending(&match_id).await;
+
+                if let Some(mut delta) = self.match_delta(&match_id).await {
+                    delta.head_to_head = true;
+                    if let Some((winner_delta, loser_delta)) = rating_deltas {
+                        for player in delta.players.iter_mut() {
+                            player.rating_delta = if player.is_winner { winner_delta } else { loser_delta };
+                        }
+                    }
+                    self.state.match_deltas.insert(&match_id, delta).expect("Lỗi lưu match delta");
+                }
+            }
+            Some(other) => {
+                info!(
+                    "[LEADERBOARD] Không ghép được match_id={} (đang chờ user={} is_winner={}, nhận user={} is_winner={}), bỏ qua rating/đối đầu",
+                    match_id, other.user_id, other.is_winner, result.user_id, result.is_winner
+                );
+            }
+            None => {
+                self.add_pending(result).await;
+            }
+        }
+    }
+
+    /// Giữ nửa kết quả chờ ghép và ghi vào chỉ mục theo người chơi.
+    /// Quá `MAX_PENDING_PER_USER` nửa đang chờ thì nửa cũ nhất bị bỏ (nửa còn lại có thể không bao giờ tới).
+    async fn add_pending(&mut self, result: TypedRecordScoreMessage) {
+        let mut match_ids = self.state.pending_by_user.get(&result.user_id).await.ok().flatten().unwrap_or_default();
+        match_ids.retain(|match_id| match_id != &result.match_id);
+        match_ids.push(result.match_id.clone());
+        while match_ids.len() > MAX_PENDING_PER_USER {
+            let evicted = match_ids.remove(0);
+            info!("[LEADERBOARD] Bỏ nửa kết quả chờ ghép quá lâu: match_id={} user={}", evicted, result.user_id);
+            self.state.pending_results.remove(&evicted).expect("Lỗi xoá pending result");
+        }
+        self.state.pending_by_user.insert(&result.user_id, match_ids).expect("Lỗi lưu chỉ mục pending result");
+        self.state.pending_results.insert(&result.match_id, result).expect("Lỗi lưu pending result");
+    }
+
+    /// Bỏ nửa kết quả đang chờ của một trận (nếu có), cả trong chỉ mục theo người chơi.
+    async fn remove_pending(&mut self, match_id: &str) {
+        let Some(pending) = self.state.pending_results.get(match_id).await.ok().flatten() else {
+            return;
+        };
+        let mut match_ids = self.state.pending_by_user.get(&pending.user_id).await.ok().flatten().unwrap_or_default();
+        match_ids.retain(|id| id != match_id);
+        if match_ids.is_empty() {
+            self.state.pending_by_user.remove(&pending.user_id).expect("Lỗi xoá chỉ mục pending result");
+        } else {
+            self.state.pending_by_user.insert(&pending.user_id, match_ids).expect("Lỗi lưu chỉ mục pending result");
+        }
+        self.state.pending_results.remove(match_id).expect("Lỗi xoá pending result");
+    }
+
+    /// Lưu bảng xếp hạng cuối mùa vào kho lưu trữ rồi reset bộ đếm.
+    /// Rating được giữ lại sang mùa mới; `processed_match_ids` cũng được giữ để vẫn chặn ghi trùng.
+    async fn close_season(&mut self) {
+        let season_id = self.state.season_id();
+
+        let mut standings = Vec::new();
+        for user_id in self.user_ids().await {
+            standings.push(self.state.entry(&user_id).await);
+        }
+        standings.sort_by(|a, b| b.score.cmp(&a.score).then(b.rating.cmp(&a.rating)).then(a.user_id.cmp(&b.user_id)));
+
+        for (index, entry) in standings.iter().enumerate() {
+            let mut history = self.state.player_seasons.get(&entry.user_id).await.ok().flatten().unwrap_or_default();
+            history.push(PlayerSeasonRecord {
+                season_id,
+                rank: index as u64 + 1,
+                entry: entry.clone(),
+            });
+            self.state.player_seasons.insert(&entry.user_id, history).expect("Lỗi lưu lịch sử mùa");
+        }
+
+        let summary = SeasonSummary {
+            season_id,
+            closed_at: self.runtime.system_time().micros(),
+            player_count: standings.len() as u64,
+            champion: standings.first().map(|entry| entry.user_id.clone()),
+        };
+        info!("[LEADERBOARD] Closing season {} with {} players", season_id, summary.player_count);
+        self.state.seasons.insert(&season_id, summary).expect("Lỗi lưu mùa giải");
+        self.state.season_standings.insert(&season_id, standings).expect("Lỗi lưu bảng xếp hạng mùa");
+
+        // Chuỗi / phong độ tính theo bảng chính nên cũng bắt đầu lại cùng mùa mới.
+        // Giữ qua mùa: rating / tier (kỹ năng), đối đầu, thống kê map / hero, và bảng có tên
+        // (`named_boards` có vòng đời riêng qua CreateBoard / ArchiveBoard, không gắn với mùa).
+        self.state.total_wins.clear();
+        self.state.total_losses.clear();
+        self.state.total_matches.clear();
+        self.state.scores.clear();
+        self.state.score_index.clear();
+        self.state.boards.clear();
+        self.state.streaks.clear();
+        self.state.player_results.clear();
+        self.state.current_season.set(season_id + 1);
+    }
+
+    /// Ghi nhận kết quả đối đầu giữa hai người chơi.
+    async fn record_head_to_head(&mut self, winner: &str, loser: &str, match_id: &str) {
+        let key = head_to_head_key(winner, loser);
+        let mut record = self.state.head_to_head.get(&key).await.ok().flatten().unwrap_or_default();
+        if key.0 == winner {
+            record.wins_a += 1;
+        } else {
+            record.wins_b += 1;
+        }
+        record.last_match_id = match_id.to_string();
+        self.state.head_to_head.insert(&key, record).expect("Lỗi lưu đối đầu");
+
+        for (user, opponent) in [(winner, loser), (loser, winner)] {
+            let mut opponents = self.state.opponents.get(user).await.ok().flatten().unwrap_or_default();
+            if !opponents.iter().any(|o| o == opponent) {
+                opponents.push(opponent.to_string());
+                self.state.opponents.insert(user, opponents).expect("Lỗi lưu danh sách đối thủ");
+            }
+        }
+    }
+
+    /// Cập nhật rating Elo cho cặp người thắng / người thua; trả về mức thay đổi (người thắng, người thua).
+    async fn update_ratings(&mut self, winner: &str, loser: &str, match_id: &str) -> (i64, i64) {
+        // Decay "lazy": trừ phần còn nợ trước khi tính trận mới, rồi đánh dấu cả hai vừa thi đấu.
+        let now = self.runtime.system_time().micros();
+        for user_id in [winner, loser] {
+            self.apply_decay(user_id, now).await;
+            self.state.last_played.insert(user_id, now).expect("Lỗi lưu last_played");
+            self.state.decayed_until.remove(user_id).expect("Lỗi xoá decayed_until");
+        }
+
+        let winner_rating = self.state.ratings.get(winner).await.ok().flatten().unwrap_or(INITIAL_RATING);
+        let loser_rating = self.state.ratings.get(loser).await.ok().flatten().unwrap_or(INITIAL_RATING);
+
+        // Người chơi đang placement dùng hệ số K riêng (lớn hơn); xem `PlacementConfig::match_update`.
+        let placement = self.state.config.get().placement.clone();
+        let winner_rated = self.state.rated_matches.get(winner).await.ok().flatten().unwrap_or_default();
+        let loser_rated = self.state.rated_matches.get(loser).await.ok().flatten().unwrap_or_default();
+        let (new_winner, new_loser) = placement.match_update(winner_rating, loser_rating, winner_rated, loser_rated);
+        info!(
+            "[LEADERBOARD] Rating match_id={}: {} {}->{}, {} {}->{}",
+            match_id, winner, winner_rating, new_winner, loser, loser_rating, new_loser
+        );
+
+        self.set_rating(winner, new_winner).await;
+        self.set_rating(loser, new_loser).await;
+        self.update_tier(winner, winner_rating, new_winner, true).await;
+        self.update_tier(loser, loser_rating, new_loser, false).await;
+        self.state.rated_matches.insert(winner, winner_rated + 1).expect("Lỗi lưu số trận rating");
+        self.state.rated_matches.insert(loser, loser_rated + 1).expect("Lỗi lưu số trận rating");
+        self.refresh_provisional(winner).await;
+        self.refresh_provisional(loser).await;
+        (new_winner as i64 - winner_rating as i64, new_loser as i64 - loser_rating as i64)
+    }
+
+    /// Lưu top `n` của bảng chính (theo score) thành snapshot bất biến `id`.
+    async fn take_snapshot(&mut self, id: String, n: u64) {
+        if self.state.snapshots.contains_key(&id).await.expect("Lỗi kiểm tra snapshot") {
+            info!("[LEADERBOARD] Snapshot {} đã tồn tại, không ghi đè", id);
+            return;
+        }
+
+        // Người chơi đang placement không có mặt trong snapshot công khai.
+        let provisional = self.state.provisional.indices().await.expect("Lỗi đọc provisional");
+        let mut user_ids = Vec::new();
+        self.state
+            .score_index
+            .for_each_index_while(|key| {
+                if !provisional.contains(&key.user_id) {
+                    user_ids.push(key.user_id);
+                }
+                Ok((user_ids.len() as u64) < n)
+            })
+            .await
+            .expect("Lỗi đọc chỉ mục score");
+
+        let mut entries = Vec::new();
+        for (index, user_id) in user_ids.iter().enumerate() {
+            entries.push(RankedEntry { rank: index as u64 + 1, entry: self.state.entry(user_id).await });
+        }
+        info!("[LEADERBOARD] Snapshot {}: top {} ({} người chơi)", id, n, entries.len());
+        let snapshot = LeaderboardSnapshot {
+            id: id.clone(),
+            season_id: self.state.season_id(),
+            taken_at: self.runtime.system_time().micros(),
+            entries,
+        };
+        self.state.snapshots.insert(&id, snapshot).expect("Lỗi lưu snapshot");
+    }
+
+    /// Thống kê và thứ hạng hiện tại của người chơi (tên cũ được chuyển sang tên hiện tại).
+    /// Như bảng xếp hạng mặc định: người chơi provisional không có thứ hạng và không được tính vào `total_players`.
+    async fn player_standing(&self, user_id: &str) -> Option<PlayerStanding> {
+        let user_id = self.state.resolve(user_id).await;
+        if !self.state.is_known(&user_id).await {
+            return None;
+        }
+        let hidden: HashSet<String> =
+            self.state.provisional.indices().await.expect("Lỗi đọc provisional").into_iter().collect();
+        let rank = match self.state.scores.get(&user_id).await.ok().flatten() {
+            Some(score) => rank_of_key(&self.state.score_index, &RankKey::new(score, &user_id), &hidden).await,
+            None => None,
+        };
+        let rating_rank = match self.state.ratings.get(&user_id).await.ok().flatten() {
+            Some(rating) => rank_of_key(&self.state.rating_index, &RankKey::new(rating, &user_id), &hidden).await,
+            None => None,
+        };
+        let mut hidden_ranked = 0u64;
+        for hidden_user in &hidden {
+            if self.state.scores.contains_key(hidden_user).await.expect("Lỗi kiểm tra score") {
+                hidden_ranked += 1;
+            }
+        }
+        let ranked = self.state.score_index.count().await.expect("Lỗi đếm chỉ mục score") as u64;
+        Some(PlayerStanding {
+            entry: self.state.entry(&user_id).await,
+            rank,
+            rating_rank,
+            total_players: ranked.saturating_sub(hidden_ranked),
+            season_id: self.state.season_id(),
+            as_of: self.runtime.system_time().micros(),
+        })
+    }
+
+    /// Trừ rating của một người chơi nếu đã không thi đấu quá lâu.
+    async fn apply_decay(&mut self, user_id: &str, now: u64) {
+        let Some(last_played) = self.state.last_played.get(user_id).await.ok().flatten() else {
+            return;
+        };
+        let Some(rating) = self.state.ratings.get(user_id).await.ok().flatten() else {
+            return;
+        };
+        let decayed_until = self.state.decayed_until.get(user_id).await.ok().flatten();
+        let decay = self.state.config.get().decay.clone();
+        let Some((new_rating, until, periods)) = decay.apply(rating, last_played, decayed_until, now) else {
+            return;
+        };
+
+        self.state.decayed_until.insert(user_id, until).expect("Lỗi lưu decayed_until");
+        if new_rating != rating {
+            info!("[LEADERBOARD] Decay {}: {} -> {} ({} chu kỳ)", user_id, rating, new_rating, periods);
+            self.set_rating(user_id, new_rating).await;
+            self.state.decay_history.push(DecayEvent {
+                user_id: user_id.to_string(),
+                old_rating: rating,
+                new_rating,
+                periods,
+                last_played,
+                timestamp: now,
+            });
+        }
+    }
+
+    /// Áp dụng decay cho mọi người chơi đã từng thi đấu.
+    async fn apply_decay_all(&mut self) {
+        let now = self.runtime.system_time().micros();
+        let user_ids = self.state.last_played.indices().await.expect("Lỗi đọc last_played");
+        for user_id in user_ids {
+            self.apply_decay(&user_id, now).await;
+        }
+    }
+
+    /// Đưa người chơi vào / ra khỏi danh sách provisional theo số trận đã tính rating.
+    async fn refresh_provisional(&mut self, user_id: &str) {
+        let rated = self.state.rated_matches.get(user_id).await.ok().flatten().unwrap_or_default();
+        if self.state.config.get().placement.is_provisional(rated) {
+            self.state.provisional.insert(&user_id.to_string()).expect("Lỗi lưu provisional");
+        } else {
+            self.state.provisional.remove(&user_id.to_string()).expect("Lỗi xoá provisional");
+        }
+    }
+
+    /// Cập nhật hạng / chuỗi thăng hạng sau khi rating thay đổi.
+    async fn update_tier(&mut self, user_id: &str, old_rating: u64, new_rating: u64, won: bool) {
+        let tiers = self.state.config.get().tiers.clone();
+        let mut tier_state = self.state.tier_state(user_id, old_rating).await;
+        let before = tiers.tier_of(tier_state.level);
+        tiers.apply(&mut tier_state, new_rating, won);
+        let after = tiers.tier_of(tier_state.level);
+        if before != after {
+            info!("[LEADERBOARD] Tier {}: {} {} -> {} {}", user_id, before.0, before.1, after.0, after.1);
+        }
+        self.state.tiers.insert(user_id, tier_state).expect("Lỗi lưu tier");
+    }
+
+    /// Ghi score mới và giữ `score_index` đồng bộ.
+    async fn set_score(&mut self, user_id: &str, score: u64) {
+        if let Some(old) = self.state.scores.get(user_id).await.ok().flatten() {
+            self.state.score_index.remove(&RankKey::new(old, user_id)).expect("Lỗi xoá chỉ mục score");
+        }
+        self.state.scores.insert(user_id, score).expect("Lỗi lưu score");
+        self.state.score_index.insert(&RankKey::new(score, user_id), ()).expect("Lỗi lưu chỉ mục score");
+    }
+
+    /// Ghi rating mới và giữ `rating_index` đồng bộ.
+    async fn set_rating(&mut self, user_id: &str, rating: u64) {
+        if let Some(old) = self.state.ratings.get(user_id).await.ok().flatten() {
+            self.state.rating_index.remove(&RankKey::new(old, user_id)).expect("Lỗi xoá chỉ mục rating");
+        }
+        self.state.ratings.insert(user_id, rating).expect("Lỗi lưu rating");
+        self.state.rating_index.insert(&RankKey::new(rating, user_id), ()).expect("Lỗi lưu chỉ mục rating");
+
+        // Score theo rating phải đi cùng rating (chỉ với người chơi đã có mặt trong mùa hiện tại).
+        if self.state.config.get().scoring.kind == ScoringKind::Rating
+            && self.state.scores.contains_key(user_id).await.expect("Lỗi kiểm tra score")
+        {
+            let score = self.state.config.get().scoring.score(0, 0, 0, rating);
+            self.set_score(user_id, score).await;
+        }
+    }
+
+    /// Xoá và dựng lại cả hai chỉ mục xếp hạng từ `scores` và `ratings`.
+    async fn rebuild_ranking_index(&mut self) {
+        self.state.score_index.clear();
+        self.state.rating_index.clear();
+
+        for user_id in self.user_ids().await {
+            let score = self.state.scores.get(&user_id).await.ok().flatten().unwrap_or_default();
+            self.state.scores.insert(&user_id, score).expect("Lỗi lưu score");
+            self.state.score_index.insert(&RankKey::new(score, &user_id), ()).expect("Lỗi lưu chỉ mục score");
+        }
+        let rated = self.state.ratings.indices().await.expect("Lỗi đọc ratings");
+        for user_id in rated {
+            if let Some(rating) = self.state.ratings.get(&user_id).await.ok().flatten() {
+                self.state.rating_index.insert(&RankKey::new(rating, &user_id), ()).expect("Lỗi lưu chỉ mục rating");
+            }
+        }
+        info!("[LEADERBOARD] Rebuilt ranking index");
+    }
+}
+
+/// Cộng bộ đếm của `from` vào `to` rồi xoá `from`.
+async fn merge_counter(map: &mut MapView<String, u64>, from: &str, to: &str) {
+    let Some(value) = map.get(from).await.ok().flatten() else {
+        return;
+    };
+    let current = map.get(to).await.ok().flatten().unwrap_or_default();
+    map.insert(to, current + value).expect("Lỗi lưu bộ đếm");
+    map.remove(from).expect("Lỗi xoá bộ đếm");
+}
+
+/// Gộp bộ đếm của `from` vào `to` trong một bảng, tính lại score bằng `score_of`.
+async fn merge_board_stats(board: &mut BoardView, from: &str, to: &str, score_of: impl Fn(&BoardStats) -> u64) {
+    let Some(from_stats) = board.stats.get(from).await.ok().flatten() else {
+        return;
+    };
+    board.score_index.remove(&RankKey::new(from_stats.score, from)).expect("Lỗi xoá chỉ mục bảng");
+    board.stats.remove(from).expect("Lỗi xoá bảng");
+
+    let mut stats = board.stats.get(to).await.ok().flatten().unwrap_or_default();
+    board.score_index.remove(&RankKey::new(stats.score, to)).expect("Lỗi xoá chỉ mục bảng");
+    stats.wins += from_stats.wins;
+    stats.losses += from_stats.losses;
+    stats.matches += from_stats.matches;
+    stats.score = score_of(&stats);
+    board.score_index.insert(&RankKey::new(stats.score, to), ()).expect("Lỗi lưu chỉ mục bảng");
+    board.stats.insert(to, stats).expect("Lỗi lưu bảng");
+}
+
+// Các phần logic dưới đây chỉ dùng trong contract, nên nằm ở đây thay vì `state.rs` (dùng chung với service).
+impl MatchDelta {
+    /// (người thắng, người thua) nếu đã nhận đủ hai phía.
+    fn pair(&self) -> Option<(&str, &str)> {
+        let winner = self.players.iter().find(|p| p.is_winner)?;
+        let loser = self.players.iter().find(|p| !p.is_winner)?;
+        Some((&winner.user_id, &loser.user_id))
+    }
+}
+
+impl WinLoss {
+    /// Cộng (`add`) hoặc trừ lại một kết quả.
+    fn apply(&mut self, won: bool, add: bool) {
+        let counter = if won { &mut self.wins } else { &mut self.losses };
+        *counter = if add { *counter + 1 } else { counter.saturating_sub(1) };
+    }
+}
+
+impl ResultHistory {
+    /// Thêm một kết quả; vượt giới hạn thì kết quả cũ nhất được gộp vào `base`.
+    fn push(&mut self, result: PlayerResult) {
+        self.results.push(result);
+        while self.results.len() > RESULT_HISTORY_LEN {
+            let oldest = self.results.remove(0);
+            self.base.record(oldest.won);
+        }
+    }
+
+    /// Bỏ một trận. Trả về false nếu không tìm thấy (chưa ghi, hoặc đã bị gộp vào `base`).
+    fn remove(&mut self, match_id: &str) -> bool {
+        let before = self.results.len();
+        self.results.retain(|result| result.match_id != match_id);
+        self.results.len() != before
+    }
+
+    /// Chuỗi / phong độ hiện tại: `base` rồi lần lượt các kết quả còn giữ.
+    fn streak(&self) -> StreakState {
+        let mut streak = self.base.clone();
+        for result in &self.results {
+            streak.record(result.won);
+        }
+        streak
+    }
+
+    /// Gộp lịch sử của hai người chơi theo thời gian trận.
+    /// Kết quả đã gộp vào `base` không còn thời gian nên không xếp xen kẽ được: giữ `base` của lịch sử
+    /// có kết quả cũ nhất còn giữ muộn hơn, chuỗi thắng tốt nhất lấy lớn nhất của hai bên.
+    fn merge(self, other: ResultHistory) -> ResultHistory {
+        let starts_at = |history: &ResultHistory| history.results.first().map_or(0, |result| result.timestamp);
+        let (mut base, other_base) = if starts_at(&self) >= starts_at(&other) {
+            (self.base, other.base)
+        } else {
+            (other.base, self.base)
+        };
+        base.best_win_streak = base.best_win_streak.max(other_base.best_win_streak);
+
+        let mut results = self.results;
+        results.extend(other.results);
+        results.sort_by_key(|result| result.timestamp);
+        let mut merged = ResultHistory { base, results: Vec::new() };
+        for result in results {
+            merged.push(result);
+        }
+        merged
+    }
+}
+
+impl BoardStats {
+    /// Score của bảng phụ theo loại trận / map: luôn là số trận thắng.
+    /// Không theo `LeaderboardConfig::scoring` (công thức đó chỉ áp dụng cho bảng chính, kể cả khi `SetScoringFormula`).
+    fn wins_score(&self) -> u64 {
+        self.wins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlayerResult, ResultHistory, RESULT_HISTORY_LEN};
+
+    fn result(index: usize, won: bool) -> PlayerResult {
+        PlayerResult { match_id: format!("m{index}"), won, timestamp: index as u64 }
+    }
+
+    #[test]
+    fn result_history_folds_results_past_the_cap() {
+        let mut history = ResultHistory::default();
+        for index in 0..RESULT_HISTORY_LEN + 3 {
+            history.push(result(index, index % 4 != 3));
+        }
+        assert_eq!(history.results.len(), RESULT_HISTORY_LEN);
+        assert_eq!(history.results[0].match_id, "m3");
+        assert_eq!(history.base.recent_results, vec![true, true, true]);
+        assert_eq!(history.streak().best_win_streak, 3);
+        // Trận đã gộp vào `base` không bỏ được nữa.
+        assert!(!history.remove("m0"));
+    }
+
+    #[test]
+    fn removing_a_result_replays_the_rest() {
+        let mut history = ResultHistory::default();
+        for (index, won) in [true, false, true].into_iter().enumerate() {
+            history.push(result(index, won));
+        }
+        assert_eq!(history.streak().current_streak, 1);
+        assert!(history.remove("m1"));
+        let streak = history.streak();
+        assert_eq!(streak.current_streak, 2);
+        assert_eq!(streak.best_win_streak, 2);
+        assert_eq!(streak.form(), "WW");
+    }
+
+    #[test]
+    fn merged_histories_follow_match_time() {
+        let mut a = ResultHistory::default();
+        let mut b = ResultHistory::default();
+        a.push(result(1, true));
+        b.push(result(2, false));
+        a.push(result(3, true));
+        let merged = b.merge(a);
+        let order = merged.results.iter().map(|result| result.match_id.as_str()).collect::<Vec<_>>();
+        assert_eq!(order, ["m1", "m2", "m3"]);
+        let streak = merged.streak();
+        assert_eq!(streak.current_streak, 1);
+        assert_eq!(streak.best_win_streak, 1);
+        assert_eq!(streak.form(), "WLW");
     }
 }
@@ -0,0 +1,155 @@
+// leaderboard/src/integrity.rs
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Phát hiện boosting / win-trading: cùng một cặp đấu liên tục trong thời gian ngắn, thắng thua luân phiên,
+//! hoặc trận kết thúc quá nhanh.
+
+use async_graphql::{InputObject, SimpleObject};
+use serde::{Deserialize, Serialize};
+
+const MICROS_PER_MINUTE: u64 = 60 * 1_000_000;
+
+/// Số trận gần nhất của một cặp được giữ lại để phát hiện.
+pub const PAIR_HISTORY_LEN: usize = 20;
+
+/// Cấu hình phát hiện, truyền vào lúc instantiate trong `LeaderboardConfig::integrity`
+/// và đổi bằng operation `SetIntegrityConfig`.
+#[derive(SimpleObject, InputObject, Clone, Debug, Serialize, Deserialize)]
+#[graphql(input_name = "IntegrityConfigInput")]
+#[serde(default)]
+pub struct IntegrityConfig {
+    /// Cửa sổ thời gian (phút) để đếm số trận của cùng một cặp; 0 = tắt kiểm tra theo cặp.
+    pub window_minutes: u64,
+    /// Cặp đấu quá số trận này trong cửa sổ thì bị gắn cờ.
+    pub max_pair_matches: u64,
+    /// Số trận liên tiếp (trong cửa sổ) thắng thua luân phiên thì bị gắn cờ; 0 = tắt.
+    pub alternating_matches: u64,
+    /// Trận ngắn hơn số giây này bị gắn cờ; 0 = tắt. Trận không có thời lượng (RecordScore) không bị xét.
+    pub min_duration_seconds: u64,
+    /// Hoàn tác trận bị gắn cờ khỏi bảng xếp hạng cho tới khi admin xác nhận (ClearFlag).
+    pub exclude_flagged: bool,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        IntegrityConfig {
+            window_minutes: 60,
+            max_pair_matches: 5,
+            alternating_matches: 4,
+            min_duration_seconds: 30,
+            exclude_flagged: false,
+        }
+    }
+}
+
+/// Một trận gần đây của một cặp đấu.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PairMatch {
+    pub match_id: String,
+    pub winner: String,
+    pub timestamp: u64, // micros
+}
+
+/// Một trận bị gắn cờ, nằm trong hàng đợi review.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct FlaggedMatch {
+    pub match_id: String,
+    pub winner: String,
+    pub loser: String,
+    pub match_type: String,
+    pub map_name: String,
+    pub reasons: Vec<String>, // "repeat_pair", "alternating_wins", "short_duration"
+    pub flagged_at: u64, // micros
+    pub excluded: bool, // đang bị hoàn tác khỏi bảng xếp hạng
+    pub cleared: bool,
+    pub cleared_by: Option<String>,
+    pub cleared_at: Option<u64>,
+}
+
+impl IntegrityConfig {
+    /// Lý do nghi ngờ của trận mới nhất; `recent` là các trận gần đây của cặp, cũ -> mới, đã gồm trận này.
+    pub fn check(&self, recent: &[PairMatch], now: u64, duration_seconds: u64) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if self.window_minutes > 0 {
+            let since = now.saturating_sub(self.window_minutes * MICROS_PER_MINUTE);
+            let window: Vec<&PairMatch> = recent.iter().filter(|m| m.timestamp >= since).collect();
+            if window.len() as u64 > self.max_pair_matches {
+                reasons.push("repeat_pair".to_string());
+            }
+            let n = self.alternating_matches as usize;
+            if n > 1 && window.len() >= n {
+                let last = &window[window.len() - n..];
+                if last.windows(2).all(|pair| pair[0].winner != pair[1].winner) {
+                    reasons.push("alternating_wins".to_string());
+                }
+            }
+        }
+        if duration_seconds > 0 && duration_seconds < self.min_duration_seconds {
+            reasons.push("short_duration".to_string());
+        }
+        reasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntegrityConfig, PairMatch, MICROS_PER_MINUTE};
+
+    fn config() -> IntegrityConfig {
+        IntegrityConfig {
+            window_minutes: 60,
+            max_pair_matches: 3,
+            alternating_matches: 0,
+            min_duration_seconds: 0,
+            exclude_flagged: false,
+        }
+    }
+
+    /// Các trận của cặp alice / bob; `winners[i]` thắng ở phút `minutes[i]`.
+    fn pair_matches(winners: &[&str], minutes: &[u64]) -> Vec<PairMatch> {
+        winners
+            .iter()
+            .zip(minutes)
+            .enumerate()
+            .map(|(index, (winner, minute))| PairMatch {
+                match_id: format!("m{index}"),
+                winner: winner.to_string(),
+                timestamp: minute * MICROS_PER_MINUTE,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repeat_pair_counts_only_matches_inside_the_window() {
+        let config = config();
+        let now = 100 * MICROS_PER_MINUTE;
+        let recent = pair_matches(&["alice"; 4], &[50, 90, 95, 100]);
+        assert_eq!(config.check(&recent, now, 0), vec!["repeat_pair".to_string()]);
+        let recent = pair_matches(&["alice"; 4], &[10, 90, 95, 100]);
+        assert!(config.check(&recent, now, 0).is_empty());
+    }
+
+    #[test]
+    fn alternating_wins_need_the_full_run() {
+        let config = IntegrityConfig { alternating_matches: 4, max_pair_matches: 10, ..config() };
+        let now = 100 * MICROS_PER_MINUTE;
+        let minutes = [97, 98, 99, 100];
+        let recent = pair_matches(&["alice", "bob", "alice", "bob"], &minutes);
+        assert_eq!(config.check(&recent, now, 0), vec!["alternating_wins".to_string()]);
+        let recent = pair_matches(&["alice", "alice", "bob", "alice"], &minutes);
+        assert!(config.check(&recent, now, 0).is_empty());
+        let recent = pair_matches(&["bob", "alice", "bob"], &minutes[1..]);
+        assert!(config.check(&recent, now, 0).is_empty());
+    }
+
+    #[test]
+    fn short_duration_ignores_unknown_durations() {
+        let config = IntegrityConfig { min_duration_seconds: 30, ..config() };
+        let now = 100 * MICROS_PER_MINUTE;
+        let recent = pair_matches(&["alice"], &[100]);
+        assert_eq!(config.check(&recent, now, 5), vec!["short_duration".to_string()]);
+        assert!(config.check(&recent, now, 30).is_empty());
+        assert!(config.check(&recent, now, 0).is_empty());
+    }
+}
@@ -5,8 +5,18 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 use serde::{Deserialize, Serialize};
-use async_graphql::{Request, Response, SimpleObject};
-use linera_sdk::linera_base_types::{ContractAbi, ServiceAbi};
+use async_graphql::{InputObject, Request, Response, SimpleObject};
+use linera_sdk::linera_base_types::{AccountOwner, ApplicationId, ChainId, ContractAbi, ServiceAbi};
+
+pub mod integrity;
+pub mod rating;
+pub mod scoring;
+pub mod tier;
+
+use integrity::IntegrityConfig;
+use rating::{DecayConfig, PlacementConfig};
+use scoring::ScoringFormula;
+use tier::{PromotionSeries, TierConfig};
 
 /// Định nghĩa dữ liệu cho một mục trong bảng xếp hạng.
 #[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
@@ -16,15 +26,406 @@ pub struct LeaderboardEntry {
     pub total_wins: u64,
     pub total_losses: u64,
     pub score: u64,
+    pub rating: u64, // Rating Elo, chỉ thay đổi khi đã nhận đủ cả hai phía của một trận.
+    pub current_streak: i64, // > 0: chuỗi thắng hiện tại, < 0: chuỗi thua hiện tại.
+    pub best_win_streak: u64,
+    pub recent_form: String, // Tối đa 10 trận gần nhất, cũ -> mới, ví dụ "WWLWL".
+    pub tier: String, // "Bronze" .. "Master"
+    pub division: u32, // 1 = bậc cao nhất trong hạng, 0 = hạng không chia bậc.
+    pub promotion_series: Option<PromotionSeries>,
+    pub provisional: bool, // Còn trong giai đoạn placement: bị ẩn khỏi bảng xếp hạng công khai.
+}
+
+/// Số người chơi trong một (hạng, bậc).
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct TierCount {
+    pub tier: String,
+    pub division: u32,
+    pub players: u64,
+}
+
+/// Số trận gần nhất được giữ lại để tính phong độ.
+pub const RECENT_FORM_LEN: usize = 10;
+
+/// Chuỗi thắng/thua và phong độ gần đây của một người chơi.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StreakState {
+    pub current_streak: i64,
+    pub best_win_streak: u64,
+    pub recent_results: Vec<bool>, // true = thắng, cũ -> mới
+}
+
+impl StreakState {
+    /// Cập nhật chuỗi và cửa sổ phong độ sau một trận.
+    pub fn record(&mut self, won: bool) {
+        self.current_streak = match (won, self.current_streak) {
+            (true, streak) if streak > 0 => streak + 1,
+            (true, _) => 1,
+            (false, streak) if streak < 0 => streak - 1,
+            (false, _) => -1,
+        };
+        if self.current_streak > 0 {
+            self.best_win_streak = self.best_win_streak.max(self.current_streak as u64);
+        }
+        self.recent_results.push(won);
+        if self.recent_results.len() > RECENT_FORM_LEN {
+            self.recent_results.remove(0);
+        }
+    }
+
+    /// Phong độ dạng chuỗi "W"/"L".
+    pub fn form(&self) -> String {
+        self.recent_results.iter().map(|&won| if won { 'W' } else { 'L' }).collect()
+    }
+}
+
+/// Thành tích đối đầu nhìn từ phía `user_id`.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct HeadToHeadRecord {
+    pub user_id: String,
+    pub opponent: String,
+    pub wins: u64,
+    pub losses: u64,
+    pub total_matches: u64,
+    pub last_match_id: String,
+}
+
+/// Thắng / thua của một người chơi trên một map hoặc với một hero.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct BreakdownStat {
+    pub key: String, // tên map hoặc hero
+    pub matches: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub win_rate: f64,
+}
+
+/// Tỉ lệ chọn / thắng toàn cục của một map hoặc hero (dùng cho cân bằng game).
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct UsageStat {
+    pub key: String,
+    pub picks: u64,
+    pub wins: u64,
+    pub pick_rate: f64, // picks / tổng số trận
+    pub win_rate: Option<f64>, // None với map (mỗi trận luôn có một người thắng)
+}
+
+/// Một mục bảng xếp hạng kèm thứ hạng (bắt đầu từ 1).
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct RankedEntry {
+    pub rank: u64,
+    pub entry: LeaderboardEntry,
+}
+
+/// Thống kê của một người chơi kèm thứ hạng trên bảng chính, tại thời điểm `as_of`.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerStanding {
+    pub entry: LeaderboardEntry,
+    pub rank: Option<u64>, // theo score; None nếu chưa được xếp hạng trong mùa hiện tại hoặc còn provisional
+    pub rating_rank: Option<u64>,
+    pub total_players: u64,
+    pub season_id: u64,
+    pub as_of: u64, // micros
+}
+
+/// Một trang bảng xếp hạng; `next_cursor` truyền vào `after` để lấy trang kế tiếp.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct LeaderboardPage {
+    pub entries: Vec<RankedEntry>,
+    pub next_cursor: Option<String>,
+    pub total_players: u64,
+}
+
+/// Một lần ghi điểm bị bỏ qua vì cặp (match_id, user_id) đã được xử lý trước đó.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct DuplicateResult {
+    pub match_id: String,
+    pub user_id: String,
+    pub is_winner: bool,
+    pub source: String, // "operation" hoặc "message"
+}
+
+/// Tóm tắt một mùa giải đã đóng.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct SeasonSummary {
+    pub season_id: u64,
+    pub closed_at: u64, // micros, lấy từ runtime.system_time()
+    pub player_count: u64,
+    pub champion: Option<String>,
+}
+
+/// Thành tích của một người chơi trong một mùa đã lưu trữ.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerSeasonRecord {
+    pub season_id: u64,
+    pub rank: u64, // bắt đầu từ 1
+    pub entry: LeaderboardEntry,
+}
+
+/// Một lần rating bị trừ vì không thi đấu.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct DecayEvent {
+    pub user_id: String,
+    pub old_rating: u64,
+    pub new_rating: u64,
+    pub periods: u64,
+    pub last_played: u64, // micros
+    pub timestamp: u64, // micros
+}
+
+/// Bản chụp top-N bất biến của bảng chính (xếp theo score), dùng để xếp hạt giống giải đấu.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct LeaderboardSnapshot {
+    pub id: String,
+    pub season_id: u64,
+    pub taken_at: u64, // micros
+    pub entries: Vec<RankedEntry>,
+}
+
+/// Số điểm tối đa giữ trong lịch sử score của một người chơi.
+pub const SCORE_HISTORY_LEN: usize = 200;
+
+/// Score / rating của một người chơi ngay sau một trận.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct ScorePoint {
+    pub timestamp: u64, // micros
+    pub score: u64,
+    pub rating: u64,
+    pub match_id: String,
+}
+
+/// Thêm một điểm vào lịch sử (ghi đè điểm cuối nếu cùng trận).
+/// Khi vượt `SCORE_HISTORY_LEN`, nửa cũ hơn được thưa bớt (giữ một điểm mỗi hai điểm) để vẫn thấy được xu hướng dài hạn.
+pub fn push_score_point(history: &mut Vec<ScorePoint>, point: ScorePoint) {
+    match history.last_mut() {
+        Some(last) if last.match_id == point.match_id => *last = point,
+        _ => history.push(point),
+    }
+    if history.len() > SCORE_HISTORY_LEN {
+        let half = history.len() / 2;
+        let mut index = 0;
+        history.retain(|_| {
+            index += 1;
+            index > half || index % 2 == 0
+        });
+    }
+}
+
+/// Một tên cũ đã được gộp / đổi sang tên mới.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerAlias {
+    pub alias: String, // tên cũ
+    pub user_id: String, // tên mới tại thời điểm gộp (có thể đã bị gộp tiếp)
+    pub action: String, // "merge" hoặc "rename"
+    pub timestamp: u64, // micros
+}
+
+/// Số thành viên tối đa của một nhóm / một truy vấn bảng xếp hạng theo nhóm.
+pub const MAX_GROUP_MEMBERS: usize = 200;
+
+/// Nhóm người chơi lưu trên leaderboard (guild, clan, bạn bè) để xếp hạng trong nhóm.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerGroup {
+    pub group_id: String,
+    pub name: String,
+    pub kind: String, // "guild", "clan", "friends", ...
+    pub members: Vec<String>,
+    pub updated_at: u64, // micros
+}
+
+/// Một lần admin huỷ hoặc sửa kết quả trận.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct MatchCorrection {
+    pub match_id: String,
+    pub action: String, // "void" hoặc "correct"
+    pub previous_winner: Option<String>,
+    pub previous_loser: Option<String>,
+    pub new_winner: Option<String>,
+    pub new_loser: Option<String>,
+    pub admin: String,
+    pub timestamp: u64, // micros
+}
+
+/// Tham số leaderboard (`--json-parameters`), quyết định ai được ghi điểm và quản trị.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Parameters {
+    /// Ứng dụng xfighter được phép ghi điểm qua `call_application` (có thể thêm sau bằng `AuthorizeApplication`).
+    pub xfighter_app_id: Option<ApplicationId>,
+    /// Các chain được phép gửi `Message` ghi điểm.
+    pub authorized_chains: Vec<ChainId>,
+    /// Các owner được phép ký operation `RecordScore`.
+    pub authorized_owners: Vec<AccountOwner>,
+    /// Các owner được phép chạy operation quản trị; rỗng = owner của chain chứa leaderboard.
+    pub admins: Vec<AccountOwner>,
+}
+
+/// Một lần ghi hoặc thao tác quản trị bị từ chối vì nguồn gửi không được phép.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct RejectedWrite {
+    pub action: String, // "RecordScore", "CloseSeason", ...
+    pub source: String, // "operation" hoặc "message"
+    pub caller: String, // ứng dụng / owner / chain đã gửi
+    pub match_id: Option<String>,
+    pub user_id: Option<String>,
+    pub timestamp: u64, // micros
+}
+
+/// Cấu hình leaderboard, truyền vào lúc instantiate (`null` = mặc định).
+#[derive(SimpleObject, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeaderboardConfig {
+    /// Các loại trận được tính vào bảng chính (score, rating); rỗng = mọi loại trận.
+    pub headline_match_types: Vec<String>,
+    /// Ngưỡng hạng / bậc / chuỗi thăng hạng.
+    pub tiers: TierConfig,
+    /// Giảm rating khi không thi đấu (mặc định tắt).
+    pub decay: DecayConfig,
+    /// Công thức tính score của bảng chính (mặc định: số trận thắng).
+    /// Bảng phụ theo loại trận / map luôn xếp theo số trận thắng; bảng có tên dùng `BoardConfig` riêng.
+    pub scoring: ScoringFormula,
+    /// Giai đoạn placement cho người chơi mới (mặc định tắt).
+    pub placement: PlacementConfig,
+    /// Phát hiện boosting / win-trading (mặc định chỉ gắn cờ, không loại khỏi bảng xếp hạng).
+    pub integrity: IntegrityConfig,
+}
+
+impl LeaderboardConfig {
+    pub fn counts_for_headline(&self, match_type: &str) -> bool {
+        self.headline_match_types.is_empty() || self.headline_match_types.iter().any(|t| t == match_type)
+    }
+}
+
+/// Chọn bảng xếp hạng phụ. Bỏ trống mọi trường = bảng chính.
+/// `board_id` chọn một bảng có tên (khu vực, sự kiện) và được ưu tiên hơn `match_type` / `map_name`.
+#[derive(InputObject, Clone, Debug, Default)]
+pub struct BoardSelector {
+    pub match_type: Option<String>,
+    pub map_name: Option<String>,
+    pub board_id: Option<String>,
+}
+
+/// Cách tính điểm của một bảng có tên.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoardConfig {
+    /// Các loại trận được tính vào bảng; rỗng = mọi loại trận.
+    pub match_types: Vec<String>,
+    pub points_per_win: u64,
+    pub points_per_loss: u64, // bị trừ, điểm không xuống dưới 0
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig { match_types: Vec::new(), points_per_win: 1, points_per_loss: 0 }
+    }
+}
+
+impl BoardConfig {
+    pub fn counts(&self, match_type: &str) -> bool {
+        self.match_types.is_empty() || self.match_types.iter().any(|t| t == match_type)
+    }
+
+    pub fn score(&self, wins: u64, losses: u64) -> u64 {
+        wins.saturating_mul(self.points_per_win).saturating_sub(losses.saturating_mul(self.points_per_loss))
+    }
+}
+
+/// Một bảng có tên (khu vực, sự kiện) trong cùng ứng dụng leaderboard.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct BoardInfo {
+    pub board_id: String,
+    pub name: String,
+    pub config: BoardConfig,
+    pub archived: bool, // Bảng đã lưu trữ không nhận kết quả mới nhưng vẫn truy vấn được.
+    pub created_at: u64, // micros
+}
+
+impl BoardSelector {
+    /// Khoá của bảng phụ tương ứng, `None` nếu là bảng chính.
+    pub fn board_key(&self) -> Option<String> {
+        match (&self.match_type, &self.map_name) {
+            (None, None) => None,
+            (Some(match_type), None) => Some(format!("type:{}", match_type)),
+            (None, Some(map_name)) => Some(format!("map:{}", map_name)),
+            (Some(match_type), Some(map_name)) => Some(format!("type:{}/map:{}", match_type, map_name)),
+        }
+    }
+}
+
+/// Các bảng phụ mà một trận đóng góp vào: theo loại trận, theo map và theo cặp (loại trận, map).
+pub fn board_keys(match_type: &str, map_name: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    if !match_type.is_empty() {
+        keys.push(format!("type:{}", match_type));
+    }
+    if !map_name.is_empty() {
+        keys.push(format!("map:{}", map_name));
+    }
+    if !match_type.is_empty() && !map_name.is_empty() {
+        keys.push(format!("type:{}/map:{}", match_type, map_name));
+    }
+    keys
 }
 
 /// Operation của leaderboard: dùng enum để chứa nhiều loại thao tác.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Operation {
-    RecordScore { user_id: String, is_winner: bool, match_id: String },
+    RecordScore {
+        user_id: String,
+        is_winner: bool,
+        match_id: String,
+        match_type: String,
+        map_name: String,
+    },
+    /// Ghi cả trận (hai người chơi, điểm số, thời lượng) trong một operation.
+    RecordMatch(MatchOutcome),
+    /// Đóng mùa hiện tại: lưu bảng xếp hạng cuối mùa rồi reset bộ đếm và chuỗi cho mùa mới (rating, bảng có tên giữ nguyên).
+    CloseSeason,
+    /// Dựng lại chỉ mục xếp hạng từ các bộ đếm (dùng cho state tạo trước khi có chỉ mục).
+    RebuildRankingIndex,
+    /// Huỷ một trận: hoàn tác mọi thay đổi (bộ đếm, bảng phụ, chuỗi, rating, đối đầu) (chỉ admin).
+    /// Kết quả gửi lại của trận này vẫn bị bỏ qua như trùng lặp.
+    VoidMatch { match_id: String },
+    /// Huỷ trận rồi ghi lại với người thắng / người thua đúng (chỉ admin).
+    CorrectMatch { match_id: String, winner: String, loser: String },
+    /// Gộp toàn bộ dữ liệu (bộ đếm, chuỗi, đối đầu, ...) của `from` vào `to` và ghi alias `from` -> `to` (chỉ admin).
+    MergePlayers { from: String, to: String },
+    /// Như `MergePlayers` nhưng `to` phải là tên chưa có dữ liệu (chỉ admin).
+    RenamePlayer { from: String, to: String },
+    /// Tạo hoặc thay toàn bộ một nhóm người chơi (tối đa `MAX_GROUP_MEMBERS` thành viên).
+    SaveGroup { group_id: String, name: String, kind: String, members: Vec<String> },
+    /// Xoá một nhóm người chơi.
+    DeleteGroup { group_id: String },
+    /// Đổi công thức tính score rồi tính lại score của mùa hiện tại từ các bộ đếm (chỉ admin).
+    /// Chỉ áp dụng cho bảng chính; bảng phụ theo loại trận / map vẫn xếp theo số trận thắng.
+    SetScoringFormula(ScoringFormula),
+    /// Đổi cấu hình phát hiện boosting / win-trading (chỉ admin); chỉ áp dụng cho các trận ghi sau đó.
+    SetIntegrityConfig(IntegrityConfig),
+    /// Admin xác nhận trận bị gắn cờ là hợp lệ; trận đang bị loại sẽ được ghi lại vào bảng xếp hạng.
+    ClearFlag { match_id: String },
+    /// Tạo một bảng có tên (chỉ admin).
+    CreateBoard { board_id: String, name: String, config: BoardConfig },
+    /// Lưu trữ một bảng có tên: ngừng nhận kết quả mới (chỉ admin).
+    ArchiveBoard { board_id: String },
+    /// Chụp top `n` của bảng chính với tên `id`; không ghi đè snapshot đã có (chỉ admin).
+    TakeSnapshot { id: String, n: u64 },
+    /// Đọc snapshot (dùng qua `call_application` từ app khác, ví dụ tournament).
+    GetSnapshot { id: String },
+    /// Đọc thống kê và thứ hạng hiện tại của một người chơi (dùng qua `call_application`, ví dụ userxfighter).
+    GetPlayerStats { user_id: String },
+    /// Trừ rating của mọi người chơi không thi đấu quá `DecayConfig::inactivity_days` (chỉ admin).
+    ApplyDecay,
+    /// Cho phép một ứng dụng (xfighter) ghi điểm qua `call_application` (admin, hoặc một ứng dụng ghi điểm
+    /// đã được cho phép: factory xfighter cấp quyền cho mỗi app con nó tạo).
+    AuthorizeApplication { application_id: ApplicationId },
+    /// Thu hồi quyền ghi điểm của một ứng dụng.
+    RevokeApplication { application_id: ApplicationId },
 }
 
 /// Message (cross-chain) triển khai dưới dạng struct để nhất quán.
+/// Dạng cũ: mỗi người chơi một message; vẫn được chấp nhận, hai nửa được ghép qua `pending_results`.
+/// Giữ nguyên ba trường để BCS của message đã gửi trước đây vẫn giải mã được.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RecordScoreMessage {
     pub user_id: String,
@@ -32,15 +433,128 @@ pub struct RecordScoreMessage {
     pub match_id: String,
 }
 
+/// Kết quả của một người chơi kèm loại trận và map (dạng leaderboard dùng để ghép hai nửa).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TypedRecordScoreMessage {
+    pub user_id: String,
+    pub is_winner: bool,
+    pub match_id: String,
+    pub match_type: String, // "Normal", "Ranked", ...
+    pub map_name: String,
+}
+
+/// Message dạng cũ không có loại trận / map: chỉ được tính vào bảng chính.
+impl From<RecordScoreMessage> for TypedRecordScoreMessage {
+    fn from(message: RecordScoreMessage) -> Self {
+        let RecordScoreMessage { user_id, is_winner, match_id } = message;
+        TypedRecordScoreMessage { user_id, is_winner, match_id, match_type: String::new(), map_name: String::new() }
+    }
+}
+
+/// Kết quả đầy đủ của một trận (cả hai người chơi), được leaderboard ghi nguyên khối.
+#[derive(SimpleObject, InputObject, Clone, Debug, Serialize, Deserialize)]
+#[graphql(input_name = "MatchOutcomeInput")]
+pub struct MatchOutcome {
+    pub match_id: String,
+    pub winner: String,
+    pub loser: String,
+    pub match_type: String,
+    pub map_name: String,
+    pub winner_score: u64,
+    pub loser_score: u64,
+    pub duration_seconds: u64,
+    pub winner_hero: Option<String>,
+    pub loser_hero: Option<String>,
+    /// Bảng có tên mà trận được tính vào (ngoài bảng chính), theo `Parameters::board_id` của xfighter.
+    pub board_id: Option<String>,
+}
+
+impl MatchOutcome {
+    /// Hai nửa (thắng, thua) của trận.
+    pub fn halves(&self) -> [TypedRecordScoreMessage; 2] {
+        [(&self.winner, true), (&self.loser, false)].map(|(user_id, is_winner)| TypedRecordScoreMessage {
+            user_id: user_id.clone(),
+            is_winner,
+            match_id: self.match_id.clone(),
+            match_type: self.match_type.clone(),
+            map_name: self.map_name.clone(),
+        })
+    }
+}
+
+/// Message cross-chain gửi tới leaderboard (và xfighter trên publisher chain chuyển tiếp).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Message {
+    /// Một người chơi (dạng cũ).
+    RecordScore(RecordScoreMessage),
+    /// Cả trận trong một message.
+    MatchOutcome(MatchOutcome),
+    /// Một người chơi kèm loại trận và map. Thêm ở cuối để không đổi chỉ số BCS của các variant trước.
+    RecordTypedScore(TypedRecordScoreMessage),
+}
+
 pub struct LeaderboardAbi;
 
+/// Phản hồi của operation, để app khác đọc dữ liệu qua `call_application`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LeaderboardResponse {
+    Ok,
+    Snapshot(Option<LeaderboardSnapshot>),
+    Stats(Option<PlayerStanding>),
+}
+
 impl ContractAbi for LeaderboardAbi {
     type Operation = Operation;
-    // Đã thay đổi về () để tránh lỗi serialization.
-    type Response = ();
+    type Response = LeaderboardResponse;
 }
 
 impl ServiceAbi for LeaderboardAbi {
     type Query = Request;
     type QueryResponse = Response;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BoardConfig, Message, RecordScoreMessage, TypedRecordScoreMessage};
+    use linera_sdk::bcs;
+
+    /// BCS của message dạng cũ: chỉ số variant 0, rồi ba trường (chuỗi có độ dài ULEB128 đứng trước).
+    #[test]
+    fn legacy_record_score_message_still_decodes() {
+        let mut bytes = vec![0, 5];
+        bytes.extend(b"alice");
+        bytes.extend([1, 2]);
+        bytes.extend(b"m1");
+        let Message::RecordScore(legacy) = bcs::from_bytes(&bytes).expect("Failed to decode a legacy message") else {
+            panic!("Legacy bytes must decode as Message::RecordScore");
+        };
+        let typed = TypedRecordScoreMessage::from(legacy);
+        assert_eq!((typed.user_id.as_str(), typed.is_winner, typed.match_id.as_str()), ("alice", true, "m1"));
+        assert!(typed.match_type.is_empty() && typed.map_name.is_empty());
+    }
+
+    #[test]
+    fn existing_variants_keep_their_bcs_index() {
+        let legacy = RecordScoreMessage { user_id: "alice".to_string(), is_winner: true, match_id: "m1".to_string() };
+        let typed = TypedRecordScoreMessage::from(legacy.clone());
+        assert_eq!(bcs::to_bytes(&Message::RecordScore(legacy)).unwrap()[0], 0);
+        assert_eq!(bcs::to_bytes(&Message::RecordTypedScore(typed)).unwrap()[0], 2);
+    }
+
+    #[test]
+    fn board_score_saturates_instead_of_overflowing() {
+        let config = BoardConfig { match_types: Vec::new(), points_per_win: 3, points_per_loss: 1 };
+        assert_eq!(config.score(4, 2), 10);
+        assert_eq!(config.score(1, 5), 0);
+        assert_eq!(config.score(u64::MAX, 0), u64::MAX);
+        assert_eq!(config.score(1, u64::MAX), 0);
+    }
+
+    #[test]
+    fn board_counts_only_configured_match_types() {
+        let ranked = BoardConfig { match_types: vec!["Ranked".to_string()], ..BoardConfig::default() };
+        assert!(ranked.counts("Ranked"));
+        assert!(!ranked.counts("Normal"));
+        assert!(BoardConfig::default().counts("Normal"));
+    }
+}
@@ -0,0 +1,205 @@
+// leaderboard/src/rating.rs
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tính rating Elo cho một cặp (người thắng, người thua) và decay khi không thi đấu.
+
+use async_graphql::SimpleObject;
+use serde::{Deserialize, Serialize};
+
+/// Rating khởi đầu của người chơi chưa hoàn tất trận nào.
+pub const INITIAL_RATING: u64 = 1500;
+
+/// Hệ số K: mức thay đổi tối đa sau một trận.
+pub const K_FACTOR: u64 = 32;
+
+/// Rating không bao giờ xuống dưới mức này.
+pub const RATING_FLOOR: u64 = 100;
+
+/// Chênh lệch tối đa được tính vào kỳ vọng (tránh trận "chắc thắng" không còn giá trị).
+const MAX_RATING_GAP: f64 = 800.0;
+
+/// Xác suất thắng kỳ vọng của `rating` khi gặp `opponent`.
+pub fn expected_score(rating: u64, opponent: u64) -> f64 {
+    let gap = (opponent as f64 - rating as f64).clamp(-MAX_RATING_GAP, MAX_RATING_GAP);
+    1.0 / (1.0 + 10f64.powf(gap / 400.0))
+}
+
+/// Trả về (rating mới của người thắng, rating mới của người thua).
+///
+/// Cả hai cùng thay đổi một lượng `delta` (tối thiểu 1 điểm), người thua bị chặn ở `RATING_FLOOR`.
+pub fn elo_update(winner: u64, loser: u64, k_factor: u64) -> (u64, u64) {
+    let expected = expected_score(winner, loser);
+    let delta = ((k_factor as f64) * (1.0 - expected)).round().max(1.0) as u64;
+    (winner + delta, loser.saturating_sub(delta).max(RATING_FLOOR))
+}
+
+/// Giai đoạn xếp hạng ban đầu (placement), truyền vào lúc instantiate trong `LeaderboardConfig::placement`.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlacementConfig {
+    /// Số trận tính rating đầu tiên mà người chơi còn là "provisional"; 0 = tắt.
+    pub matches: u64,
+    /// Hệ số K dùng cho người chơi provisional (rating biến động mạnh hơn để nhanh về đúng mức).
+    pub k_factor: u64,
+}
+
+impl Default for PlacementConfig {
+    fn default() -> Self {
+        PlacementConfig { matches: 0, k_factor: 2 * K_FACTOR }
+    }
+}
+
+impl PlacementConfig {
+    /// Người chơi đã có `rated_matches` trận tính rating có còn trong giai đoạn placement không.
+    pub fn is_provisional(&self, rated_matches: u64) -> bool {
+        rated_matches < self.matches
+    }
+
+    /// Hệ số K cho một người chơi.
+    pub fn k_factor(&self, rated_matches: u64) -> u64 {
+        if self.is_provisional(rated_matches) {
+            self.k_factor
+        } else {
+            K_FACTOR
+        }
+    }
+
+    /// Rating mới sau một trận, mỗi người chơi dùng hệ số K của chính mình.
+    ///
+    /// Cố ý không zero-sum khi chỉ một bên còn provisional: người mới thay đổi nhanh để về đúng mức,
+    /// còn người chơi đã ổn định chỉ thắng / mất theo `K_FACTOR`, không bị kéo theo rating chưa chính xác của đối thủ.
+    pub fn match_update(&self, winner: u64, loser: u64, winner_rated: u64, loser_rated: u64) -> (u64, u64) {
+        let (new_winner, _) = elo_update(winner, loser, self.k_factor(winner_rated));
+        let (_, new_loser) = elo_update(winner, loser, self.k_factor(loser_rated));
+        (new_winner, new_loser)
+    }
+}
+
+const MICROS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000;
+
+/// Cấu hình giảm rating khi không thi đấu, truyền vào lúc instantiate trong `LeaderboardConfig::decay`.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecayConfig {
+    /// Số ngày không thi đấu trước khi bắt đầu trừ rating; 0 = tắt.
+    pub inactivity_days: u64,
+    /// Cứ mỗi `period_days` ngày tiếp theo trừ `points_per_period` điểm.
+    pub period_days: u64,
+    pub points_per_period: u64,
+    /// Decay không kéo rating xuống dưới mức này.
+    pub floor: u64,
+}
+
+impl Default for DecayConfig {
+    fn default() -> Self {
+        DecayConfig { inactivity_days: 0, period_days: 7, points_per_period: 25, floor: INITIAL_RATING }
+    }
+}
+
+impl DecayConfig {
+    /// Rating sau decay tại thời điểm `now` (micros), cùng mốc thời gian đã decay tới và số chu kỳ bị trừ.
+    ///
+    /// `decayed_until` là mốc của lần decay trước (nếu có) để không trừ lặp cùng một chu kỳ.
+    pub fn apply(&self, rating: u64, last_played: u64, decayed_until: Option<u64>, now: u64) -> Option<(u64, u64, u64)> {
+        if self.inactivity_days == 0 || self.points_per_period == 0 {
+            return None;
+        }
+        let period = self.period_days.max(1) * MICROS_PER_DAY;
+        let start = last_played.saturating_add(self.inactivity_days * MICROS_PER_DAY);
+        let from = decayed_until.unwrap_or(start).max(start);
+        if now <= from {
+            return None;
+        }
+        let periods = (now - from) / period;
+        if periods == 0 {
+            return None;
+        }
+        let floor = self.floor.max(RATING_FLOOR).min(rating);
+        let new_rating = rating.saturating_sub(self.points_per_period.saturating_mul(periods)).max(floor);
+        Some((new_rating, from + periods * period, periods))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        elo_update, expected_score, DecayConfig, PlacementConfig, INITIAL_RATING, K_FACTOR, MICROS_PER_DAY,
+        RATING_FLOOR,
+    };
+
+    #[test]
+    fn equal_ratings_split_the_k_factor() {
+        assert_eq!(elo_update(1500, 1500, K_FACTOR), (1516, 1484));
+        assert!((expected_score(1600, 1400) + expected_score(1400, 1600) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn upsets_move_ratings_more() {
+        assert_eq!(elo_update(1200, 1600, K_FACTOR), (1229, 1571));
+        assert_eq!(elo_update(1600, 1200, K_FACTOR), (1603, 1197));
+        assert_eq!(elo_update(1500, 1500, 2 * K_FACTOR), (1532, 1468));
+    }
+
+    #[test]
+    fn lopsided_wins_still_move_one_point_and_respect_the_floor() {
+        // Chênh lệch bị chặn ở 800 điểm: người thắng vẫn nhận tối thiểu 1 điểm.
+        assert_eq!(elo_update(3000, 1000, K_FACTOR), (3001, 999));
+        assert_eq!(elo_update(1500, RATING_FLOOR, K_FACTOR), (1501, RATING_FLOOR));
+    }
+
+    #[test]
+    fn each_player_uses_their_own_k_factor() {
+        let placement = PlacementConfig { matches: 5, k_factor: 2 * K_FACTOR };
+        // Người thắng còn provisional (K = 64), người thua đã ổn định (K = 32): không zero-sum.
+        assert_eq!(placement.match_update(1500, 1500, 0, 10), (1532, 1484));
+        assert_eq!(placement.match_update(1500, 1500, 10, 0), (1516, 1468));
+        // Cả hai cùng giai đoạn thì vẫn zero-sum như `elo_update`.
+        assert_eq!(placement.match_update(1500, 1500, 10, 10), elo_update(1500, 1500, K_FACTOR));
+    }
+
+    fn days(n: u64) -> u64 {
+        n * MICROS_PER_DAY
+    }
+
+    /// Bắt đầu trừ sau 14 ngày, mỗi 7 ngày tiếp theo trừ 25 điểm.
+    fn decay() -> DecayConfig {
+        DecayConfig { inactivity_days: 14, period_days: 7, points_per_period: 25, floor: INITIAL_RATING }
+    }
+
+    #[test]
+    fn decay_is_off_by_default() {
+        assert_eq!(DecayConfig::default().apply(1800, 0, None, days(365)), None);
+    }
+
+    #[test]
+    fn decay_counts_whole_periods_after_inactivity() {
+        let config = decay();
+        assert_eq!(config.apply(1800, 0, None, days(14)), None);
+        assert_eq!(config.apply(1800, 0, None, days(20)), None);
+        assert_eq!(config.apply(1800, 0, None, days(21)), Some((1775, days(21), 1)));
+        assert_eq!(config.apply(1800, 0, None, days(34)), Some((1750, days(28), 2)));
+    }
+
+    #[test]
+    fn decayed_until_prevents_charging_a_period_twice() {
+        let config = decay();
+        let (rating, until, _) = config.apply(1800, 0, None, days(22)).expect("one period is due");
+        assert_eq!((rating, until), (1775, days(21)));
+        assert_eq!(config.apply(rating, 0, Some(until), days(22)), None);
+        assert_eq!(config.apply(rating, 0, Some(until), days(27)), None);
+        assert_eq!(config.apply(rating, 0, Some(until), days(28)), Some((1750, days(28), 1)));
+        // Mốc cũ hơn thời điểm bắt đầu trừ không làm trừ sớm.
+        assert_eq!(config.apply(1800, days(10), Some(days(3)), days(30)), None);
+    }
+
+    #[test]
+    fn decay_stops_at_the_floor() {
+        let config = decay();
+        assert_eq!(config.apply(1520, 0, None, days(49)), Some((1500, days(49), 5)));
+        // Đã dưới mức sàn thì không bị trừ thêm.
+        assert_eq!(config.apply(1400, 0, None, days(49)), Some((1400, days(49), 5)));
+        let no_floor = DecayConfig { floor: 0, ..decay() };
+        assert_eq!(no_floor.apply(150, 0, None, days(49)), Some((RATING_FLOOR, days(49), 5)));
+    }
+}
@@ -0,0 +1,92 @@
+// leaderboard/src/scoring.rs
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Công thức tính score của bảng chính.
+
+use async_graphql::{Enum, InputObject, SimpleObject};
+use serde::{Deserialize, Serialize};
+
+/// Loại công thức tính score.
+#[derive(Enum, Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringKind {
+    /// Score = số trận thắng (mặc định, giống hành vi cũ).
+    #[default]
+    WinsOnly,
+    /// Score = thắng - thua (không xuống dưới 0).
+    WinsMinusLosses,
+    /// Score = thắng * `points_per_win` + thua * `points_per_loss` + hoà * `points_per_draw` (không xuống dưới 0).
+    Points,
+    /// Score = rating Elo hiện tại.
+    Rating,
+}
+
+/// Công thức tính score, truyền vào lúc instantiate trong `LeaderboardConfig::scoring`
+/// và đổi bằng operation `SetScoringFormula`.
+#[derive(SimpleObject, InputObject, Clone, Debug, Serialize, Deserialize)]
+#[graphql(input_name = "ScoringFormulaInput")]
+#[serde(default)]
+pub struct ScoringFormula {
+    pub kind: ScoringKind,
+    /// Chỉ dùng với `Points`.
+    pub points_per_win: i64,
+    pub points_per_loss: i64,
+    pub points_per_draw: i64,
+}
+
+impl Default for ScoringFormula {
+    fn default() -> Self {
+        ScoringFormula { kind: ScoringKind::WinsOnly, points_per_win: 3, points_per_loss: 0, points_per_draw: 1 }
+    }
+}
+
+impl ScoringFormula {
+    /// Score từ các bộ đếm của mùa hiện tại và rating.
+    pub fn score(&self, wins: u64, losses: u64, draws: u64, rating: u64) -> u64 {
+        match self.kind {
+            ScoringKind::WinsOnly => wins,
+            ScoringKind::WinsMinusLosses => wins.saturating_sub(losses),
+            ScoringKind::Points => {
+                let points = wins as i64 * self.points_per_win
+                    + losses as i64 * self.points_per_loss
+                    + draws as i64 * self.points_per_draw;
+                points.max(0) as u64
+            }
+            ScoringKind::Rating => rating,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScoringFormula, ScoringKind};
+
+    fn formula(kind: ScoringKind) -> ScoringFormula {
+        ScoringFormula { kind, ..ScoringFormula::default() }
+    }
+
+    #[test]
+    fn wins_only_ignores_losses_and_rating() {
+        assert_eq!(formula(ScoringKind::WinsOnly).score(7, 12, 3, 1800), 7);
+    }
+
+    #[test]
+    fn wins_minus_losses_does_not_go_below_zero() {
+        let formula = formula(ScoringKind::WinsMinusLosses);
+        assert_eq!(formula.score(7, 3, 0, 1500), 4);
+        assert_eq!(formula.score(3, 7, 0, 1500), 0);
+    }
+
+    #[test]
+    fn points_weigh_each_result() {
+        assert_eq!(formula(ScoringKind::Points).score(4, 2, 3, 1500), 4 * 3 + 3);
+        let penalised = ScoringFormula { kind: ScoringKind::Points, points_per_win: 2, points_per_loss: -1, points_per_draw: 0 };
+        assert_eq!(penalised.score(5, 3, 1, 1500), 7);
+        assert_eq!(penalised.score(1, 5, 0, 1500), 0);
+    }
+
+    #[test]
+    fn rating_uses_the_current_rating() {
+        assert_eq!(formula(ScoringKind::Rating).score(10, 0, 0, 1623), 1623);
+    }
+}
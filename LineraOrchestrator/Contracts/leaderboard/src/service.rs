@@ -6,17 +6,27 @@
 
 mod state;
 
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
-use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
+use log::error;
+use async_graphql::{EmptySubscription, Enum, Object, Request, Response, Schema};
 use linera_sdk::{
     abi::WithServiceAbi,
-    views::View,
+    linera_base_types::ApplicationId,
+    views::{linera_views::collection_view::ReadGuardedView, MapView, View},
     Service, ServiceRuntime,
     bcs,
 };
-use leaderboard::{LeaderboardAbi, LeaderboardEntry, Operation};
-use self::state::LeaderboardState;
-use std::collections::HashSet;
+use leaderboard::{
+    BoardConfig, BoardInfo, BoardSelector, BreakdownStat, DecayEvent, DuplicateResult, HeadToHeadRecord, LeaderboardAbi,
+    LeaderboardConfig, LeaderboardEntry, LeaderboardPage, LeaderboardSnapshot, MAX_GROUP_MEMBERS, MatchCorrection,
+    MatchOutcome, Operation, PlayerAlias, PlayerGroup, PlayerSeasonRecord, RankedEntry, RejectedWrite, ScorePoint,
+    SeasonSummary, TierCount, UsageStat,
+};
+use leaderboard::integrity::{FlaggedMatch, IntegrityConfig};
+use leaderboard::rating::INITIAL_RATING;
+use leaderboard::scoring::ScoringFormula;
+use self::state::{head_to_head_key, processed_key, rank_of_key, BoardView, LeaderboardState, RankKey, WinLoss};
 
 // Dữ liệu sẽ được truyền vào `Schema` để thực hiện truy vấn.
 pub struct LeaderboardService {
@@ -65,16 +75,162 @@ struct MutationRoot {
 #[Object]
 impl MutationRoot {
     /// Ghi điểm chính thức
-    async fn record_score(&self, user_id: String, is_winner: bool, match_id: String) -> bool {
+    async fn record_score(
+        &self,
+        user_id: String,
+        is_winner: bool,
+        match_id: String,
+        match_type: Option<String>,
+        map_name: Option<String>,
+    ) -> bool {
         // Tạo enum Operation rồi để runtime tự BCS-serialize (không tự serialize thành Vec<u8>)
-        let op = Operation::RecordScore { user_id, is_winner, match_id };
+        let op = Operation::RecordScore {
+            user_id,
+            is_winner,
+            match_id,
+            match_type: match_type.unwrap_or_default(),
+            map_name: map_name.unwrap_or_default(),
+        };
         self.runtime.schedule_operation(&op);
         true
     }
 
+    /// Ghi cả trận (hai người chơi) trong một operation.
+    async fn record_match(&self, outcome: MatchOutcome) -> bool {
+        self.runtime.schedule_operation(&Operation::RecordMatch(outcome));
+        true
+    }
+
+    /// Dựng lại chỉ mục xếp hạng (cho state cũ).
+    async fn rebuild_ranking_index(&self) -> bool {
+        self.runtime.schedule_operation(&Operation::RebuildRankingIndex);
+        true
+    }
+
+    /// Cho phép ứng dụng xfighter ghi điểm qua `call_application` (chỉ admin).
+    async fn authorize_application(&self, application_id: ApplicationId) -> bool {
+        self.runtime.schedule_operation(&Operation::AuthorizeApplication { application_id });
+        true
+    }
+
+    /// Thu hồi quyền ghi điểm của một ứng dụng (chỉ admin).
+    async fn revoke_application(&self, application_id: ApplicationId) -> bool {
+        self.runtime.schedule_operation(&Operation::RevokeApplication { application_id });
+        true
+    }
+
+    /// Huỷ một trận và hoàn tác mọi thay đổi nó đã tạo ra (chỉ admin).
+    async fn void_match(&self, match_id: String) -> bool {
+        self.runtime.schedule_operation(&Operation::VoidMatch { match_id });
+        true
+    }
+
+    /// Sửa kết quả một trận: huỷ rồi ghi lại với người thắng / người thua đúng (chỉ admin).
+    async fn correct_match(&self, match_id: String, winner: String, loser: String) -> bool {
+        self.runtime.schedule_operation(&Operation::CorrectMatch { match_id, winner, loser });
+        true
+    }
+
+    /// Gộp toàn bộ dữ liệu của `from` vào `to`; kết quả cũ mang tên `from` sẽ trỏ về `to` (chỉ admin).
+    async fn merge_players(&self, from: String, to: String) -> bool {
+        self.runtime.schedule_operation(&Operation::MergePlayers { from, to });
+        true
+    }
+
+    /// Đổi tên người chơi sang một tên chưa có dữ liệu (chỉ admin).
+    async fn rename_player(&self, from: String, to: String) -> bool {
+        self.runtime.schedule_operation(&Operation::RenamePlayer { from, to });
+        true
+    }
+
+    /// Tạo hoặc thay toàn bộ một nhóm người chơi (guild / clan / bạn bè).
+    async fn save_group(&self, group_id: String, name: String, kind: String, members: Vec<String>) -> bool {
+        self.runtime.schedule_operation(&Operation::SaveGroup { group_id, name, kind, members });
+        true
+    }
+
+    async fn delete_group(&self, group_id: String) -> bool {
+        self.runtime.schedule_operation(&Operation::DeleteGroup { group_id });
+        true
+    }
+
+    /// Đổi công thức tính score và tính lại score mùa hiện tại (chỉ admin).
+    async fn set_scoring_formula(&self, formula: ScoringFormula) -> bool {
+        self.runtime.schedule_operation(&Operation::SetScoringFormula(formula));
+        true
+    }
+
+    /// Đổi cấu hình phát hiện boosting / win-trading (chỉ admin).
+    async fn set_integrity_config(&self, config: IntegrityConfig) -> bool {
+        self.runtime.schedule_operation(&Operation::SetIntegrityConfig(config));
+        true
+    }
+
+    /// Xác nhận trận bị gắn cờ là hợp lệ và ghi lại nếu đang bị loại (chỉ admin).
+    async fn clear_flag(&self, match_id: String) -> bool {
+        self.runtime.schedule_operation(&Operation::ClearFlag { match_id });
+        true
+    }
+
+    /// Tạo bảng có tên (khu vực, sự kiện) với cách tính điểm riêng (chỉ admin).
+    async fn create_board(
+        &self,
+        board_id: String,
+        name: String,
+        match_types: Option<Vec<String>>,
+        points_per_win: Option<u64>,
+        points_per_loss: Option<u64>,
+    ) -> bool {
+        let defaults = BoardConfig::default();
+        let config = BoardConfig {
+            match_types: match_types.unwrap_or_default(),
+            points_per_win: points_per_win.unwrap_or(defaults.points_per_win),
+            points_per_loss: points_per_loss.unwrap_or(defaults.points_per_loss),
+        };
+        self.runtime.schedule_operation(&Operation::CreateBoard { board_id, name, config });
+        true
+    }
+
+    /// Lưu trữ bảng có tên (chỉ admin).
+    async fn archive_board(&self, board_id: String) -> bool {
+        self.runtime.schedule_operation(&Operation::ArchiveBoard { board_id });
+        true
+    }
+
+    /// Chụp top `n` của bảng chính thành snapshot bất biến `id` (chỉ admin).
+    async fn take_snapshot(&self, id: String, n: u64) -> bool {
+        self.runtime.schedule_operation(&Operation::TakeSnapshot { id, n });
+        true
+    }
+
+    /// Trừ rating của người chơi không thi đấu quá lâu (chỉ admin).
+    async fn apply_decay(&self) -> bool {
+        self.runtime.schedule_operation(&Operation::ApplyDecay);
+        true
+    }
+
+    /// Đóng mùa hiện tại và bắt đầu mùa mới.
+    async fn close_season(&self) -> bool {
+        self.runtime.schedule_operation(&Operation::CloseSeason);
+        true
+    }
+
     /// Debug: Trả về hex bytes của Operation theo BCS (không gửi)
-    async fn debug_operation(&self, user_id: String, is_winner: bool, match_id: String) -> String {
-        let op = Operation::RecordScore { user_id, is_winner, match_id };
+    async fn debug_operation(
+        &self,
+        user_id: String,
+        is_winner: bool,
+        match_id: String,
+        match_type: Option<String>,
+        map_name: Option<String>,
+    ) -> String {
+        let op = Operation::RecordScore {
+            user_id,
+            is_winner,
+            match_id,
+            match_type: match_type.unwrap_or_default(),
+            map_name: map_name.unwrap_or_default(),
+        };
         let bytes = bcs::to_bytes(&op).expect("Cannot serialize Operation to BCS");
         bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join("")
     }
@@ -84,40 +240,671 @@ struct QueryRoot {
     state: Arc<LeaderboardState>,
 }
 
+/// Tiêu chí sắp xếp bảng xếp hạng.
+#[derive(Enum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+enum RankingOrder {
+    /// Theo `score` (mặc định, giữ nguyên hành vi cũ).
+    #[default]
+    Score,
+    /// Theo rating Elo.
+    Rating,
+}
+
 #[Object]
 impl QueryRoot {
     // Đã thay đổi kiểu trả về thành u64
     async fn score(&self, user_id: String) -> Option<u64> {
+        let user_id = self.state.resolve(&user_id).await;
         self.state.scores.get(&user_id).await.ok().flatten()
     }
 
-    async fn leaderboard(&self, limit: Option<u64>) -> Vec<LeaderboardEntry> {
-        let mut entries = Vec::new();
+    async fn rating(&self, user_id: String) -> u64 {
+        let user_id = self.state.resolve(&user_id).await;
+        self.state.ratings.get(&user_id).await.ok().flatten().unwrap_or(INITIAL_RATING)
+    }
+
+    /// Thống kê đầy đủ của một người chơi (bộ đếm, rating, chuỗi thắng, phong độ).
+    /// Tên cũ đã gộp / đổi được tự động chuyển sang tên hiện tại.
+    async fn player_stats(&self, user_id: String) -> Option<LeaderboardEntry> {
+        let user_id = self.state.resolve(&user_id).await;
+        if !self.state.is_known(&user_id).await {
+            return None;
+        }
+        Some(self.state.entry(&user_id).await)
+    }
+
+    /// Bảng xếp hạng đọc trực tiếp từ chỉ mục đã sắp xếp; chỉ các mục trong trang mới được tra cứu.
+    /// `board` chọn bảng phụ theo loại trận / map (khi đó xếp theo số trận thắng trong bảng phụ, không theo `scoring`).
+    /// Người chơi provisional (đang placement) bị ẩn trừ khi `include_provisional` = true.
+    async fn leaderboard(
+        &self,
+        limit: Option<u64>,
+        offset: Option<u64>,
+        order_by: Option<RankingOrder>,
+        board: Option<BoardSelector>,
+        include_provisional: Option<bool>,
+    ) -> Vec<LeaderboardEntry> {
+        let Some(board) = self.load_board(board).await else {
+            return Vec::new();
+        };
+        let ranking = self.ranking(order_by, board.as_deref());
+        let hidden = self.hidden_players(include_provisional).await;
+        let keys = index_range(ranking.index(), None, offset.unwrap_or(0), limit, &hidden).await;
+        ranking.entries(keys).await.into_iter().map(|ranked| ranked.entry).collect()
+    }
+
+    /// Phân trang theo cursor: truyền `nextCursor` của trang trước vào `after`.
+    /// Trang càng sâu càng tốn: chỉ mục vẫn được duyệt từ đầu để đánh số thứ hạng.
+    async fn leaderboard_page(
+        &self,
+        first: u64,
+        after: Option<String>,
+        order_by: Option<RankingOrder>,
+        board: Option<BoardSelector>,
+        include_provisional: Option<bool>,
+    ) -> LeaderboardPage {
+        let Some(board) = self.load_board(board).await else {
+            return LeaderboardPage { entries: Vec::new(), next_cursor: None, total_players: 0 };
+        };
+        let ranking = self.ranking(order_by, board.as_deref());
+        let hidden = self.hidden_players(include_provisional).await;
+        let after = after.as_deref().and_then(decode_cursor);
+        let keys = index_range(ranking.index(), after.as_ref(), 0, Some(first), &hidden).await;
+        let mut hidden_count = 0;
+        for user_id in &hidden {
+            if ranking.key_of(user_id).await.is_some() {
+                hidden_count += 1;
+            }
+        }
+        let next_cursor = if keys.len() as u64 == first {
+            keys.last().map(|(_, key)| encode_cursor(key))
+        } else {
+            None
+        };
+        LeaderboardPage {
+            entries: ranking.entries(keys).await,
+            next_cursor,
+            total_players: (ranking.index().count().await.unwrap_or_default() as u64).saturating_sub(hidden_count),
+        }
+    }
+
+    /// Thứ hạng (bắt đầu từ 1) của một người chơi; chi phí tăng theo thứ hạng (duyệt chỉ mục từ đầu).
+    /// Như `leaderboard`: người chơi provisional không được xếp hạng và không chiếm thứ hạng, trừ khi `include_provisional`.
+    async fn rank_of(
+        &self,
+        user_id: String,
+        order_by: Option<RankingOrder>,
+        board: Option<BoardSelector>,
+        include_provisional: Option<bool>,
+    ) -> Option<u64> {
+        let user_id = self.state.resolve(&user_id).await;
+        let board = self.load_board(board).await?;
+        let ranking = self.ranking(order_by, board.as_deref());
+        let key = ranking.key_of(&user_id).await?;
+        let hidden = self.hidden_players(include_provisional).await;
+        rank_of_key(ranking.index(), &key, &hidden).await
+    }
 
-        // Thu thập tất cả các ID từ các map
-        let mut user_ids = self.state.scores.indices().await.unwrap_or_default().into_iter().collect::<HashSet<_>>();
-        let wins_ids = self.state.total_wins.indices().await.unwrap_or_default();
-        let losses_ids = self.state.total_losses.indices().await.unwrap_or_default();
-        let matches_ids = self.state.total_matches.indices().await.unwrap_or_default();
-        
-        user_ids.extend(wins_ids.into_iter());
-        user_ids.extend(losses_ids.into_iter());
-        user_ids.extend(matches_ids.into_iter());
+    /// Các người chơi xếp ngay trên và dưới `user_id`, tối đa `radius` mỗi phía.
+    /// Người chơi provisional bị ẩn trừ khi `include_provisional` = true (kể cả chính `user_id`).
+    async fn around(
+        &self,
+        user_id: String,
+        radius: u64,
+        order_by: Option<RankingOrder>,
+        board: Option<BoardSelector>,
+        include_provisional: Option<bool>,
+    ) -> Vec<RankedEntry> {
+        let user_id = self.state.resolve(&user_id).await;
+        let Some(board) = self.load_board(board).await else {
+            return Vec::new();
+        };
+        let ranking = self.ranking(order_by, board.as_deref());
+        let Some(key) = ranking.key_of(&user_id).await else {
+            return Vec::new();
+        };
+        let hidden = self.hidden_players(include_provisional).await;
+        let Some(rank) = rank_of_key(ranking.index(), &key, &hidden).await else {
+            return Vec::new();
+        };
+        let offset = (rank - 1).saturating_sub(radius);
+        let limit = (rank - 1 - offset) + radius + 1;
+        let keys = index_range(ranking.index(), None, offset, Some(limit), &hidden).await;
+        ranking.entries(keys).await
+    }
 
-        for user_id in user_ids {
-            // Đã sửa lỗi: Dùng 0 thay vì 0.0 để khớp kiểu dữ liệu u64
-            let score = self.state.scores.get(&user_id).await.ok().flatten().unwrap_or(0);
-            let total_matches = self.state.total_matches.get(&user_id).await.ok().flatten().unwrap_or_default();
-            let total_wins = self.state.total_wins.get(&user_id).await.ok().flatten().unwrap_or_default();
-            let total_losses = self.state.total_losses.get(&user_id).await.ok().flatten().unwrap_or_default();
-            
-            entries.push(LeaderboardEntry { user_id, score, total_matches, total_wins, total_losses });
+    /// Xếp hạng trong một nhóm: truyền `user_ids` hoặc `group_id` của nhóm đã lưu (có thể cả hai).
+    /// Mỗi mục mang thứ hạng toàn bảng; người chơi chưa được xếp hạng bị bỏ qua,
+    /// người chơi provisional cũng vậy trừ khi `include_provisional` = true.
+    async fn group_leaderboard(
+        &self,
+        user_ids: Option<Vec<String>>,
+        group_id: Option<String>,
+        order_by: Option<RankingOrder>,
+        board: Option<BoardSelector>,
+        include_provisional: Option<bool>,
+    ) -> Vec<RankedEntry> {
+        let mut members = user_ids.unwrap_or_default();
+        if let Some(group_id) = group_id {
+            if let Some(group) = self.state.groups.get(&group_id).await.ok().flatten() {
+                members.extend(group.members);
+            }
         }
+        let Some(board) = self.load_board(board).await else {
+            return Vec::new();
+        };
+        let ranking = self.ranking(order_by, board.as_deref());
+        let hidden = self.hidden_players(include_provisional).await;
 
-        // Đã sửa lỗi: Sắp xếp bằng cmp thay vì partial_cmp vì score là u64
-        entries.sort_by(|a, b| b.score.cmp(&a.score));
+        let mut seen = Vec::new();
+        let mut ranked = HashSet::new();
+        for user_id in members {
+            let user_id = self.state.resolve(&user_id).await;
+            if seen.contains(&user_id) {
+                continue;
+            }
+            if seen.len() >= MAX_GROUP_MEMBERS {
+                break;
+            }
+            if !hidden.contains(&user_id) && ranking.key_of(&user_id).await.is_some() {
+                ranked.insert(user_id.clone());
+            }
+            seen.push(user_id);
+        }
+        let keys = member_ranks(ranking.index(), &ranked, &hidden).await;
+        ranking.entries(keys).await
+    }
+
+    async fn group(&self, group_id: String) -> Option<PlayerGroup> {
+        self.state.groups.get(&group_id).await.ok().flatten()
+    }
+
+    /// Các nhóm đã lưu, lọc theo thành viên nếu có.
+    async fn groups(&self, member: Option<String>) -> Vec<PlayerGroup> {
+        let mut groups = Vec::new();
+        for group_id in self.state.groups.indices().await.unwrap_or_default() {
+            let Some(group) = self.state.groups.get(&group_id).await.ok().flatten() else {
+                continue;
+            };
+            if member.as_ref().is_none_or(|member| group.members.contains(member)) {
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    /// Thành tích đối đầu của `a` khi gặp `b`.
+    /// Các truy vấn theo người chơi đều chuyển tên cũ (đã gộp / đổi) sang tên hiện tại, như `player_stats`.
+    async fn head_to_head(&self, a: String, b: String) -> HeadToHeadRecord {
+        let (a, b) = (self.state.resolve(&a).await, self.state.resolve(&b).await);
+        self.head_to_head_record(&a, &b).await
+    }
+
+    /// Các đối thủ `user_id` gặp nhiều nhất.
+    async fn rivals(&self, user_id: String, limit: Option<u64>) -> Vec<HeadToHeadRecord> {
+        let user_id = self.state.resolve(&user_id).await;
+        let opponents = self.state.opponents.get(&user_id).await.ok().flatten().unwrap_or_default();
+        let mut records = Vec::with_capacity(opponents.len());
+        for opponent in opponents {
+            records.push(self.head_to_head_record(&user_id, &opponent).await);
+        }
+        records.sort_by(|a, b| b.total_matches.cmp(&a.total_matches).then(a.opponent.cmp(&b.opponent)));
+        let limit = limit.unwrap_or(records.len() as u64) as usize;
+        records.into_iter().take(limit).collect()
+    }
+
+    /// Cấu hình leaderboard (loại trận tính vào bảng chính, ...).
+    async fn config(&self) -> LeaderboardConfig {
+        self.state.config.get().clone()
+    }
+
+    /// Số người chơi (đã có rating) trong từng hạng / bậc, từ hạng cao xuống thấp.
+    async fn tier_distribution(&self) -> Vec<TierCount> {
+        let tiers = &self.state.config.get().tiers;
+        let mut counts = BTreeMap::<u32, u64>::new();
+        for user_id in self.state.ratings.indices().await.unwrap_or_default() {
+            let rating = self.state.ratings.get(&user_id).await.ok().flatten().unwrap_or(INITIAL_RATING);
+            let level = self.state.tier_state(&user_id, rating).await.level;
+            *counts.entry(level).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .rev()
+            .map(|(level, players)| {
+                let (tier, division) = tiers.tier_of(level);
+                TierCount { tier, division, players }
+            })
+            .collect()
+    }
+
+    /// Các bảng có tên (kể cả đã lưu trữ).
+    async fn named_boards(&self, include_archived: Option<bool>) -> Vec<BoardInfo> {
+        let include_archived = include_archived.unwrap_or(true);
+        let mut boards = Vec::new();
+        for board_id in self.state.board_registry.indices().await.unwrap_or_default() {
+            if let Some(board) = self.state.board_registry.get(&board_id).await.ok().flatten() {
+                if include_archived || !board.archived {
+                    boards.push(board);
+                }
+            }
+        }
+        boards
+    }
+
+    /// Thông tin một bảng có tên.
+    async fn board_info(&self, board_id: String) -> Option<BoardInfo> {
+        self.state.board_registry.get(&board_id).await.ok().flatten()
+    }
+
+    /// Danh sách khoá các bảng phụ đang có dữ liệu.
+    async fn boards(&self) -> Vec<String> {
+        self.state.boards.indices().await.unwrap_or_default()
+    }
+
+    /// Thắng / thua của người chơi theo từng map.
+    async fn player_map_stats(&self, user_id: String) -> Vec<BreakdownStat> {
+        let user_id = self.state.resolve(&user_id).await;
+        let breakdown = self.state.player_breakdowns.get(&user_id).await.ok().flatten().unwrap_or_default();
+        breakdown_stats(breakdown.maps)
+    }
+
+    /// Thắng / thua của người chơi theo từng hero.
+    async fn player_hero_stats(&self, user_id: String) -> Vec<BreakdownStat> {
+        let user_id = self.state.resolve(&user_id).await;
+        let breakdown = self.state.player_breakdowns.get(&user_id).await.ok().flatten().unwrap_or_default();
+        breakdown_stats(breakdown.heroes)
+    }
+
+    /// Tỉ lệ chọn các map trên toàn bộ trận đã ghép, giảm dần theo lượt chọn.
+    async fn map_stats(&self) -> Vec<UsageStat> {
+        let total = *self.state.usage_matches.get();
+        let mut stats = Vec::new();
+        for map_name in self.state.map_usage.indices().await.unwrap_or_default() {
+            let picks = self.state.map_usage.get(&map_name).await.ok().flatten().unwrap_or_default();
+            stats.push(UsageStat { key: map_name, picks, wins: 0, pick_rate: ratio(picks, total), win_rate: None });
+        }
+        stats.sort_by(|a, b| b.picks.cmp(&a.picks).then(a.key.cmp(&b.key)));
+        stats
+    }
+
+    /// Tỉ lệ chọn / thắng của các hero; pick rate = số trận có hero / tổng số trận.
+    async fn hero_stats(&self) -> Vec<UsageStat> {
+        let total = *self.state.usage_matches.get();
+        let mut stats = Vec::new();
+        for hero in self.state.hero_usage.indices().await.unwrap_or_default() {
+            let usage = self.state.hero_usage.get(&hero).await.ok().flatten().unwrap_or_default();
+            let picks = usage.wins + usage.losses;
+            stats.push(UsageStat {
+                key: hero,
+                picks,
+                wins: usage.wins,
+                pick_rate: ratio(picks, total),
+                win_rate: Some(ratio(usage.wins, picks)),
+            });
+        }
+        stats.sort_by(|a, b| b.picks.cmp(&a.picks).then(a.key.cmp(&b.key)));
+        stats
+    }
+
+    /// Kết quả đầy đủ (điểm số, thời lượng) của một trận đã nhận qua `MatchOutcome`.
+    async fn match_outcome(&self, match_id: String) -> Option<MatchOutcome> {
+        self.state.match_outcomes.get(&match_id).await.ok().flatten()
+    }
+
+    /// Snapshot top-N theo id.
+    async fn snapshot(&self, id: String) -> Option<LeaderboardSnapshot> {
+        self.state.snapshots.get(&id).await.ok().flatten()
+    }
+
+    /// Id của các snapshot đã chụp.
+    async fn snapshots(&self) -> Vec<String> {
+        self.state.snapshots.indices().await.unwrap_or_default()
+    }
+
+    /// Score / rating của người chơi sau từng trận, lọc theo khoảng thời gian `[from, to]` (micros) nếu có.
+    /// Lịch sử bị giới hạn độ dài: các điểm cũ được thưa bớt.
+    async fn score_history(&self, user_id: String, from: Option<u64>, to: Option<u64>) -> Vec<ScorePoint> {
+        let user_id = self.state.resolve(&user_id).await;
+        let history = self.state.score_history.get(&user_id).await.ok().flatten().unwrap_or_default();
+        history
+            .into_iter()
+            .filter(|point| from.is_none_or(|from| point.timestamp >= from) && to.is_none_or(|to| point.timestamp <= to))
+            .collect()
+    }
+
+    /// Hàng đợi review: các trận bị gắn cờ, mới nhất trước. Mặc định chỉ các trận chưa được xác nhận.
+    async fn review_queue(&self, include_cleared: Option<bool>) -> Vec<FlaggedMatch> {
+        let include_cleared = include_cleared.unwrap_or(false);
+        let mut flags = Vec::new();
+        for match_id in self.state.flagged_matches.indices().await.unwrap_or_default() {
+            if let Some(flag) = self.state.flagged_matches.get(&match_id).await.ok().flatten() {
+                if include_cleared || !flag.cleared {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.sort_by(|a, b| b.flagged_at.cmp(&a.flagged_at));
+        flags
+    }
+
+    async fn flagged_match(&self, match_id: String) -> Option<FlaggedMatch> {
+        self.state.flagged_matches.get(&match_id).await.ok().flatten()
+    }
+
+    /// Tên hiện tại của một người chơi (đi theo các lần gộp / đổi tên).
+    async fn resolve_player(&self, user_id: String) -> String {
+        self.state.resolve(&user_id).await
+    }
+
+    /// Bảng alias: các tên cũ đã được gộp / đổi, lọc theo tên đích nếu có.
+    async fn aliases(&self, user_id: Option<String>) -> Vec<PlayerAlias> {
+        let mut aliases = Vec::new();
+        for alias in self.state.aliases.indices().await.unwrap_or_default() {
+            let Some(entry) = self.state.aliases.get(&alias).await.ok().flatten() else {
+                continue;
+            };
+            if user_id.as_ref().is_none_or(|user_id| &entry.user_id == user_id) {
+                aliases.push(entry);
+            }
+        }
+        aliases
+    }
+
+    /// Nhật ký huỷ / sửa trận, lọc theo `match_id` nếu có.
+    async fn corrections(&self, match_id: Option<String>) -> Vec<MatchCorrection> {
+        let count = self.state.corrections.count();
+        let corrections = self.state.corrections.read(0..count).await.unwrap_or_default();
+        corrections
+            .into_iter()
+            .filter(|correction| match_id.as_ref().is_none_or(|match_id| &correction.match_id == match_id))
+            .collect()
+    }
+
+    /// Lịch sử decay rating, lọc theo `user_id` nếu có.
+    async fn decay_history(&self, user_id: Option<String>) -> Vec<DecayEvent> {
+        let count = self.state.decay_history.count();
+        let events = self.state.decay_history.read(0..count).await.unwrap_or_default();
+        events
+            .into_iter()
+            .filter(|event| user_id.as_ref().is_none_or(|user_id| &event.user_id == user_id))
+            .collect()
+    }
+
+    /// Thời điểm (micros) trận tính rating gần nhất của người chơi.
+    async fn last_played(&self, user_id: String) -> Option<u64> {
+        let user_id = self.state.resolve(&user_id).await;
+        self.state.last_played.get(&user_id).await.ok().flatten()
+    }
+
+    /// Các lần ghi điểm trùng (match_id, user_id) đã bị leaderboard bỏ qua.
+    async fn duplicate_results(&self) -> Vec<DuplicateResult> {
+        let count = self.state.duplicate_results.count();
+        self.state.duplicate_results.read(0..count).await.unwrap_or_default()
+    }
+
+    /// Các lần ghi điểm / thao tác quản trị bị từ chối vì nguồn gửi không được phép.
+    async fn rejected_writes(&self) -> Vec<RejectedWrite> {
+        let count = self.state.rejected_writes.count();
+        self.state.rejected_writes.read(0..count).await.unwrap_or_default()
+    }
+
+    /// Các ứng dụng được admin cho phép ghi điểm.
+    async fn writer_applications(&self) -> Vec<String> {
+        let apps = self.state.writer_apps.indices().await.unwrap_or_default();
+        apps.into_iter().map(|app| app.to_string()).collect()
+    }
+
+    /// Kiểm tra kết quả của `user_id` trong trận `match_id` đã được ghi nhận chưa.
+    async fn is_processed(&self, match_id: String, user_id: String) -> bool {
+        let key = processed_key(&match_id, &user_id);
+        self.state.processed_match_ids.contains_key(&key).await.unwrap_or(false)
+    }
+
+    /// ID mùa đang diễn ra.
+    async fn current_season(&self) -> u64 {
+        self.state.season_id()
+    }
+
+    /// Các mùa đã đóng, theo thứ tự season_id.
+    async fn seasons(&self) -> Vec<SeasonSummary> {
+        let mut seasons = Vec::new();
+        let ids = self.state.seasons.indices().await.unwrap_or_default();
+        for id in ids {
+            if let Some(season) = self.state.seasons.get(&id).await.ok().flatten() {
+                seasons.push(season);
+            }
+        }
+        seasons.sort_by_key(|season| season.season_id);
+        seasons
+    }
+
+    /// Bảng xếp hạng cuối mùa đã được lưu trữ.
+    async fn season_standings(&self, season_id: u64, limit: Option<u64>) -> Vec<LeaderboardEntry> {
+        let standings = self.state.season_standings.get(&season_id).await.ok().flatten().unwrap_or_default();
+        let limit = limit.unwrap_or(standings.len() as u64) as usize;
+        standings.into_iter().take(limit).collect()
+    }
+
+    /// Thành tích của một người chơi qua các mùa đã đóng.
+    async fn player_season_history(&self, user_id: String) -> Vec<PlayerSeasonRecord> {
+        let user_id = self.state.resolve(&user_id).await;
+        self.state.player_seasons.get(&user_id).await.ok().flatten().unwrap_or_default()
+    }
+}
+
+impl QueryRoot {
+    async fn head_to_head_record(&self, user_id: &str, opponent: &str) -> HeadToHeadRecord {
+        let key = head_to_head_key(user_id, opponent);
+        let record = self.state.head_to_head.get(&key).await.ok().flatten().unwrap_or_default();
+        let (wins, losses) = if key.0 == user_id {
+            (record.wins_a, record.wins_b)
+        } else {
+            (record.wins_b, record.wins_a)
+        };
+        HeadToHeadRecord {
+            user_id: user_id.to_string(),
+            opponent: opponent.to_string(),
+            wins,
+            losses,
+            total_matches: wins + losses,
+            last_match_id: record.last_match_id,
+        }
+    }
+
+    /// `None` nếu selector trỏ tới bảng phụ chưa có dữ liệu; `Some(None)` là bảng chính.
+    /// Người chơi bị ẩn khỏi bảng xếp hạng: những người đang placement, trừ khi `include_provisional`.
+    async fn hidden_players(&self, include_provisional: Option<bool>) -> HashSet<String> {
+        if include_provisional.unwrap_or(false) {
+            return HashSet::new();
+        }
+        self.state.provisional.indices().await.unwrap_or_default().into_iter().collect()
+    }
+
+    async fn load_board(&self, selector: Option<BoardSelector>) -> Option<Option<ReadGuardedView<BoardView>>> {
+        if let Some(board_id) = selector.as_ref().and_then(|selector| selector.board_id.clone()) {
+            return match self.state.named_boards.try_load_entry(&board_id).await {
+                Ok(Some(board)) => Some(Some(board)),
+                Ok(None) => None,
+                Err(e) => {
+                    error!("[LEADERBOARD] Lỗi tải bảng {}: {:?}", board_id, e);
+                    None
+                }
+            };
+        }
+        let Some(board_key) = selector.and_then(|selector| selector.board_key()) else {
+            return Some(None);
+        };
+        match self.state.boards.try_load_entry(&board_key).await {
+            Ok(Some(board)) => Some(Some(board)),
+            Ok(None) => None,
+            Err(e) => {
+                error!("[LEADERBOARD] Lỗi tải bảng phụ {}: {:?}", board_key, e);
+                None
+            }
+        }
+    }
+
+    fn ranking<'a>(&'a self, order_by: Option<RankingOrder>, board: Option<&'a BoardView>) -> Ranking<'a> {
+        match board {
+            Some(board) => Ranking::Board(&self.state, board),
+            None => Ranking::Headline(&self.state, order_by.unwrap_or_default()),
+        }
+    }
+}
+
+/// Bảng đang được truy vấn: bảng chính (theo score hoặc rating) hoặc một bảng phụ.
+enum Ranking<'a> {
+    Headline(&'a LeaderboardState, RankingOrder),
+    Board(&'a LeaderboardState, &'a BoardView),
+}
+
+impl Ranking<'_> {
+    fn index(&self) -> &MapView<RankKey, ()> {
+        match self {
+            Ranking::Headline(state, RankingOrder::Score) => &state.score_index,
+            Ranking::Headline(state, RankingOrder::Rating) => &state.rating_index,
+            Ranking::Board(_, board) => &board.score_index,
+        }
+    }
+
+    /// Khoá của người chơi trong chỉ mục tương ứng, `None` nếu chưa được xếp hạng.
+    async fn key_of(&self, user_id: &str) -> Option<RankKey> {
+        let value = match self {
+            Ranking::Headline(state, RankingOrder::Score) => state.scores.get(user_id).await.ok().flatten(),
+            Ranking::Headline(state, RankingOrder::Rating) => state.ratings.get(user_id).await.ok().flatten(),
+            Ranking::Board(_, board) => board.stats.get(user_id).await.ok().flatten().map(|stats| stats.score),
+        }?;
+        Some(RankKey::new(value, user_id))
+    }
+
+    async fn entries(&self, keys: Vec<(u64, RankKey)>) -> Vec<RankedEntry> {
+        let mut entries = Vec::with_capacity(keys.len());
+        for (rank, key) in keys {
+            let entry = match self {
+                Ranking::Headline(state, _) => state.entry(&key.user_id).await,
+                Ranking::Board(state, board) => {
+                    // Bộ đếm lấy từ bảng phụ, rating và chuỗi thắng lấy từ bảng chính.
+                    let stats = board.stats.get(&key.user_id).await.ok().flatten().unwrap_or_default();
+                    let mut entry = state.entry(&key.user_id).await;
+                    entry.total_matches = stats.matches;
+                    entry.total_wins = stats.wins;
+                    entry.total_losses = stats.losses;
+                    entry.score = stats.score;
+                    entry
+                }
+            };
+            entries.push(RankedEntry { rank, entry });
+        }
+        entries
+    }
+}
+
+/// Duyệt chỉ mục theo thứ tự, bỏ qua các khoá tới `after` (nếu có) và `offset` khoá tiếp theo,
+/// rồi lấy tối đa `limit` khoá kèm thứ hạng. Dừng ngay khi đủ `limit`.
+/// Để đánh số thứ hạng, trang nào cũng duyệt từ đầu chỉ mục (O(thứ hạng cuối trang)); các khoá đứng trước
+/// `after` chỉ được so sánh bằng `RankKey::cmp`, không serialize lại.
+async fn index_range(
+    index: &MapView<RankKey, ()>,
+    after: Option<&RankKey>,
+    offset: u64,
+    limit: Option<u64>,
+    hidden: &HashSet<String>,
+) -> Vec<(u64, RankKey)> {
+    let mut keys = Vec::new();
+    let mut rank = 0u64;
+    let mut skipped = 0u64;
+    if limit == Some(0) {
+        return keys;
+    }
+    let result = index
+        .for_each_index_while(|key| {
+            // Người chơi bị ẩn không chiếm thứ hạng.
+            if hidden.contains(&key.user_id) {
+                return Ok(true);
+            }
+            rank += 1;
+            if let Some(after) = after {
+                if &key <= after {
+                    return Ok(true);
+                }
+            }
+            if skipped < offset {
+                skipped += 1;
+                return Ok(true);
+            }
+            keys.push((rank, key));
+            Ok(limit.map_or(true, |limit| (keys.len() as u64) < limit))
+        })
+        .await;
+    if let Err(e) = result {
+        error!("[LEADERBOARD] Lỗi duyệt chỉ mục xếp hạng: {:?}", e);
+    }
+    keys
+}
+
+/// Thứ hạng của các thành viên trong `members` (đều có trong chỉ mục), theo thứ tự xếp hạng.
+/// Duyệt chỉ mục một lần và dừng khi đã gặp đủ thành viên, thay vì đếm lại từ đầu cho từng người.
+async fn member_ranks(
+    index: &MapView<RankKey, ()>,
+    members: &HashSet<String>,
+    hidden: &HashSet<String>,
+) -> Vec<(u64, RankKey)> {
+    let mut keys = Vec::new();
+    let mut rank = 0u64;
+    if members.is_empty() {
+        return keys;
+    }
+    let result = index
+        .for_each_index_while(|key| {
+            if hidden.contains(&key.user_id) {
+                return Ok(true);
+            }
+            rank += 1;
+            if members.contains(&key.user_id) {
+                keys.push((rank, key));
+            }
+            Ok(keys.len() < members.len())
+        })
+        .await;
+    if let Err(e) = result {
+        error!("[LEADERBOARD] Lỗi duyệt chỉ mục xếp hạng: {:?}", e);
+    }
+    keys
+}
+
+/// `part / total`, 0 nếu chưa có dữ liệu.
+fn ratio(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+fn breakdown_stats(counters: BTreeMap<String, WinLoss>) -> Vec<BreakdownStat> {
+    counters
+        .into_iter()
+        .map(|(key, counter)| {
+            let matches = counter.wins + counter.losses;
+            let win_rate = ratio(counter.wins, matches);
+            BreakdownStat { key, matches, wins: counter.wins, losses: counter.losses, win_rate }
+        })
+        .collect()
+}
+
+/// Cursor là hex của khoá chỉ mục (BCS) của mục cuối cùng trong trang.
+fn encode_cursor(key: &RankKey) -> String {
+    let bytes = bcs::to_bytes(key).expect("Cannot serialize RankKey to BCS");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join("")
+}
 
-        let limit = limit.unwrap_or(entries.len() as u64) as usize;
-        entries.into_iter().take(limit).collect()
+fn decode_cursor(cursor: &str) -> Option<RankKey> {
+    if cursor.len() % 2 != 0 {
+        return None;
     }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    bcs::from_bytes(&bytes).ok()
 }
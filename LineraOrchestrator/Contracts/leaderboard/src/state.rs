@@ -2,7 +2,192 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_sdk::views::{linera_views, MapView, RootView, ViewStorageContext};
+use linera_sdk::linera_base_types::ApplicationId;
+use linera_sdk::views::{
+    linera_views, CollectionView, LogView, MapView, RegisterView, RootView, SetView, View, ViewStorageContext,
+};
+use leaderboard::integrity::{FlaggedMatch, PairMatch};
+use leaderboard::rating::INITIAL_RATING;
+use leaderboard::tier::TierState;
+use leaderboard::{
+    BoardInfo, DecayEvent, DuplicateResult, LeaderboardConfig, LeaderboardEntry, LeaderboardSnapshot, MatchCorrection,
+    MatchOutcome, PlayerAlias, PlayerGroup, PlayerSeasonRecord, RejectedWrite, ScorePoint, SeasonSummary, StreakState,
+    TypedRecordScoreMessage,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+
+/// Khoá của chỉ mục xếp hạng.
+/// `MapView` duyệt khoá theo thứ tự byte sau khi serialize (BCS), nên điểm được lưu
+/// big-endian và đảo bit để người điểm cao đứng trước; `user_id` phá thế hoà.
+/// `Ord` cho cùng thứ tự đó để so sánh khoá mà không phải serialize lại.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankKey {
+    pub inverted_value: [u8; 8],
+    pub user_id: String,
+}
+
+impl RankKey {
+    pub fn new(value: u64, user_id: &str) -> Self {
+        RankKey {
+            inverted_value: (!value).to_be_bytes(),
+            user_id: user_id.to_string(),
+        }
+    }
+}
+
+impl Ord for RankKey {
+    /// Điểm (đã đảo) trước, rồi `user_id` như BCS: độ dài (ULEB128) trước nội dung, nên tên ngắn hơn đứng trước.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inverted_value
+            .cmp(&other.inverted_value)
+            .then_with(|| uleb128(self.user_id.len()).cmp(&uleb128(other.user_id.len())))
+            .then_with(|| self.user_id.as_bytes().cmp(other.user_id.as_bytes()))
+    }
+}
+
+impl PartialOrd for RankKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Độ dài chuỗi dạng ULEB128, như BCS ghi trước nội dung.
+fn uleb128(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Thành tích đối đầu giữa hai người chơi; `a`, `b` theo thứ tự của `head_to_head_key`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HeadToHead {
+    pub wins_a: u64,
+    pub wins_b: u64,
+    pub last_match_id: String,
+}
+
+/// Khoá của `head_to_head`: cặp người chơi đã sắp xếp để (a, b) và (b, a) trùng nhau.
+pub fn head_to_head_key(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/// Khoá idempotency của `processed_match_ids`: (match_id, user_id).
+/// Dùng tuple thay vì nối chuỗi vì cả hai giá trị đều có thể chứa ':'.
+pub fn processed_key(match_id: &str, user_id: &str) -> (String, String) {
+    (match_id.to_string(), user_id.to_string())
+}
+
+/// Đếm vị trí của `target` trong chỉ mục, chỉ duyệt các khoá đứng trước nó.
+/// Người chơi trong `hidden` không chiếm thứ hạng (như `index_range` của service); `target` bị ẩn thì trả về None.
+/// Chi phí vẫn là O(thứ hạng): người chơi càng thấp trên bảng thì càng nhiều khoá phải đọc.
+pub async fn rank_of_key(index: &MapView<RankKey, ()>, target: &RankKey, hidden: &HashSet<String>) -> Option<u64> {
+    if hidden.contains(&target.user_id) {
+        return None;
+    }
+    let mut rank = 0u64;
+    let mut found = false;
+    index
+        .for_each_index_while(|key| {
+            if hidden.contains(&key.user_id) {
+                return Ok(true);
+            }
+            rank += 1;
+            match key.cmp(target) {
+                Ordering::Less => Ok(true),
+                Ordering::Equal => {
+                    found = true;
+                    Ok(false)
+                }
+                Ordering::Greater => Ok(false),
+            }
+        })
+        .await
+        .ok()?;
+    found.then_some(rank)
+}
+
+/// Giới hạn độ dài chuỗi alias (chặn vòng lặp).
+const MAX_ALIAS_HOPS: usize = 16;
+
+/// Thay đổi mà một trận đã tạo ra cho một người chơi.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerDelta {
+    pub user_id: String,
+    pub is_winner: bool,
+    pub headline: bool, // Đã cộng vào bộ đếm / chuỗi của bảng chính.
+    pub rating_delta: i64,
+}
+
+/// Mọi thay đổi một trận đã tạo ra, để `VoidMatch` / `CorrectMatch` có thể hoàn tác.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MatchDelta {
+    pub season_id: u64, // Bộ đếm chỉ được hoàn tác nếu trận thuộc mùa hiện tại.
+    pub match_type: String,
+    pub map_name: String,
+    pub players: Vec<PlayerDelta>,
+    pub head_to_head: bool, // Đã ghi vào thành tích đối đầu.
+    pub heroes: Vec<(String, String)>, // (user_id, hero) khi nhận qua MatchOutcome.
+    pub board_id: Option<String>, // Bảng có tên đã được cộng điểm.
+}
+
+/// Thắng / thua trên một map hoặc với một hero.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WinLoss {
+    pub wins: u64,
+    pub losses: u64,
+}
+
+/// Thống kê theo map / hero của một người chơi.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PlayerBreakdown {
+    pub maps: BTreeMap<String, WinLoss>,
+    pub heroes: BTreeMap<String, WinLoss>,
+}
+
+/// Một kết quả bảng chính của người chơi.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerResult {
+    pub match_id: String,
+    pub won: bool,
+    pub timestamp: u64, // micros, để gộp lịch sử hai người chơi theo thời gian.
+}
+
+/// Lịch sử kết quả bảng chính (đã giới hạn độ dài), để tính lại chuỗi / phong độ khi huỷ / sửa trận.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResultHistory {
+    pub base: StreakState, // Chuỗi tính tới trước kết quả đầu tiên còn giữ trong `results`.
+    pub results: Vec<PlayerResult>, // Tối đa `RESULT_HISTORY_LEN` (contract) kết quả, cũ -> mới.
+}
+
+/// Bộ đếm của một người chơi trong một bảng phụ.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BoardStats {
+    pub wins: u64,
+    pub losses: u64,
+    pub matches: u64,
+    pub score: u64,
+}
+
+/// Một bảng xếp hạng phụ (theo loại trận và/hoặc map).
+#[derive(View)]
+#[view(context = ViewStorageContext)]
+pub struct BoardView {
+    pub stats: MapView<String, BoardStats>, // user_id => bộ đếm trong bảng này.
+    pub score_index: MapView<RankKey, ()>, // Chỉ mục xếp hạng theo score của bảng này.
+}
 
 /// Định nghĩa trạng thái của hợp đồng Leaderboard.
 #[derive(RootView)]
@@ -11,6 +196,129 @@ pub struct LeaderboardState {
     pub total_wins: MapView<String, u64>, // Lưu trữ tổng số trận thắng của mỗi người chơi.
     pub total_losses: MapView<String, u64>,  // Lưu trữ tổng số trận thua của mỗi người chơi.
     pub total_matches: MapView<String, u64>,  // Lưu trữ tổng số trận đấu của mỗi người chơi.
-    pub scores: MapView<String, u64>, // Lưu trữ điểm số chính của người chơi (thắng - thua).
-    pub processed_match_ids: MapView<String, bool>, // Lưu trữ các ID trận đấu đã được xử lý để tránh trùng lặp.
+    pub scores: MapView<String, u64>, // Điểm số chính của người chơi, tính theo `LeaderboardConfig::scoring`.
+    pub processed_match_ids: MapView<(String, String), bool>, // (match_id, user_id) đã được xử lý, dùng để chặn ghi trùng.
+    pub ratings: MapView<String, u64>, // Rating Elo của mỗi người chơi.
+    pub match_outcomes: MapView<String, MatchOutcome>, // match_id => kết quả đầy đủ (điểm số, thời lượng) khi nhận qua MatchOutcome.
+    pub match_deltas: MapView<String, MatchDelta>, // match_id => thay đổi trận đã tạo ra (để huỷ / sửa).
+    pub player_results: MapView<String, ResultHistory>, // user_id => các kết quả bảng chính gần nhất (đã giới hạn độ dài).
+    pub corrections: LogView<MatchCorrection>, // Nhật ký huỷ / sửa trận.
+    pub streaks: MapView<String, StreakState>, // Chuỗi thắng/thua và phong độ 10 trận gần nhất.
+    pub head_to_head: MapView<(String, String), HeadToHead>, // Cặp người chơi (đã sắp xếp) => thành tích đối đầu.
+    pub opponents: MapView<String, Vec<String>>, // user_id => các đối thủ đã từng gặp.
+    pub pending_results: MapView<String, TypedRecordScoreMessage>, // match_id => nửa kết quả đang chờ phía còn lại để tính rating.
+    pub pending_by_user: MapView<String, Vec<String>>, // user_id => match_id các nửa kết quả đang chờ, cũ -> mới.
+    pub duplicate_results: LogView<DuplicateResult>, // Các lần ghi trùng đã bị bỏ qua.
+    pub score_index: MapView<RankKey, ()>, // Chỉ mục xếp hạng theo score (giảm dần).
+    pub rating_index: MapView<RankKey, ()>, // Chỉ mục xếp hạng theo rating (giảm dần).
+    pub last_played: MapView<String, u64>, // user_id => thời điểm (micros) trận tính rating gần nhất.
+    pub decayed_until: MapView<String, u64>, // user_id => mốc thời gian đã decay tới (xoá khi thi đấu lại).
+    pub decay_history: LogView<DecayEvent>, // Lịch sử decay.
+    pub tiers: MapView<String, TierState>, // user_id => hạng, chuỗi thăng hạng, lượt bảo vệ.
+    pub config: RegisterView<LeaderboardConfig>, // Cấu hình truyền vào lúc instantiate.
+    pub writer_apps: SetView<ApplicationId>, // Ứng dụng được admin cho phép ghi điểm (ngoài Parameters::xfighter_app_id).
+    pub factory_apps: SetView<ApplicationId>, // Writer app do admin cấp quyền trực tiếp: được cấp quyền tiếp cho app con.
+    pub rejected_writes: LogView<RejectedWrite>, // Các lần ghi / quản trị bị từ chối.
+    pub board_registry: MapView<String, BoardInfo>, // board_id => thông tin bảng có tên.
+    pub named_boards: CollectionView<String, BoardView>, // board_id => dữ liệu bảng có tên (không reset theo mùa).
+    pub boards: CollectionView<String, BoardView>, // "type:<match_type>", "map:<map_name>", "type:<..>/map:<..>" => bảng phụ.
+    pub player_breakdowns: MapView<String, PlayerBreakdown>, // user_id => thắng / thua theo map và hero.
+    pub map_usage: MapView<String, u64>, // map_name => số trận (đã ghép đủ hai phía).
+    pub hero_usage: MapView<String, WinLoss>, // hero => số lần được chọn thắng / thua.
+    pub usage_matches: RegisterView<u64>, // Tổng số trận đã ghép, mẫu số của pick rate.
+    pub snapshots: MapView<String, LeaderboardSnapshot>, // snapshot_id => top-N bất biến.
+    pub current_season: RegisterView<u64>, // ID mùa đang diễn ra (bắt đầu từ 1).
+    pub seasons: MapView<u64, SeasonSummary>, // season_id => tóm tắt mùa đã đóng.
+    pub season_standings: MapView<u64, Vec<LeaderboardEntry>>, // season_id => bảng xếp hạng cuối mùa (đã sắp xếp).
+    pub player_seasons: MapView<String, Vec<PlayerSeasonRecord>>, // user_id => thành tích qua các mùa.
+    pub aliases: MapView<String, PlayerAlias>, // tên cũ => tên mới (sau MergePlayers / RenamePlayer).
+    pub groups: MapView<String, PlayerGroup>, // group_id => nhóm người chơi (guild / clan / bạn bè).
+    pub rated_matches: MapView<String, u64>, // user_id => số trận đã tính rating (không reset theo mùa).
+    pub provisional: SetView<String>, // người chơi còn trong giai đoạn placement.
+    pub pair_matches: MapView<(String, String), Vec<PairMatch>>, // head_to_head_key => các trận gần nhất của cặp.
+    pub flagged_matches: MapView<String, FlaggedMatch>, // match_id => trận bị gắn cờ (hàng đợi review).
+    pub score_history: MapView<String, Vec<ScorePoint>>, // user_id => score / rating sau từng trận (đã giới hạn độ dài).
+}
+
+impl LeaderboardState {
+    /// Ghép các bộ đếm của một người chơi thành `LeaderboardEntry`.
+    pub async fn entry(&self, user_id: &str) -> LeaderboardEntry {
+        let streak = self.streaks.get(user_id).await.ok().flatten().unwrap_or_default();
+        let rating = self.ratings.get(user_id).await.ok().flatten().unwrap_or(INITIAL_RATING);
+        let tier_state = self.tier_state(user_id, rating).await;
+        let (tier, division) = self.config.get().tiers.tier_of(tier_state.level);
+        LeaderboardEntry {
+            user_id: user_id.to_string(),
+            score: self.scores.get(user_id).await.ok().flatten().unwrap_or(0),
+            total_matches: self.total_matches.get(user_id).await.ok().flatten().unwrap_or_default(),
+            total_wins: self.total_wins.get(user_id).await.ok().flatten().unwrap_or_default(),
+            total_losses: self.total_losses.get(user_id).await.ok().flatten().unwrap_or_default(),
+            rating,
+            current_streak: streak.current_streak,
+            best_win_streak: streak.best_win_streak,
+            recent_form: streak.form(),
+            tier,
+            division,
+            promotion_series: tier_state.promotion,
+            provisional: self.provisional.contains(user_id).await.unwrap_or(false),
+        }
+    }
+
+    /// Trạng thái hạng đã lưu; người chơi chưa có thì xếp theo rating hiện tại.
+    pub async fn tier_state(&self, user_id: &str, rating: u64) -> TierState {
+        match self.tiers.get(user_id).await.ok().flatten() {
+            Some(state) => state,
+            None => self.config.get().tiers.initial_state(rating),
+        }
+    }
+
+    /// Tên hiện tại của một người chơi, đi theo chuỗi alias (tối đa `MAX_ALIAS_HOPS` bước).
+    pub async fn resolve(&self, user_id: &str) -> String {
+        let mut current = user_id.to_string();
+        for _ in 0..MAX_ALIAS_HOPS {
+            match self.aliases.get(&current).await.ok().flatten() {
+                Some(alias) => current = alias.user_id,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// Người chơi đã có dữ liệu trong leaderboard.
+    pub async fn is_known(&self, user_id: &str) -> bool {
+        self.total_matches.contains_key(user_id).await.unwrap_or(false)
+            || self.ratings.contains_key(user_id).await.unwrap_or(false)
+    }
+
+    /// ID mùa hiện tại; state cũ chưa có mùa được coi là mùa 1.
+    pub fn season_id(&self) -> u64 {
+        (*self.current_season.get()).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RankKey;
+    use linera_sdk::bcs;
+
+    #[test]
+    fn rank_key_order_matches_bcs_bytes() {
+        let long = "x".repeat(127);
+        let longer = "a".repeat(128);
+        let mut keys = vec![
+            RankKey::new(5, "bob"),
+            RankKey::new(5, "alice"),
+            RankKey::new(5, "al"),
+            RankKey::new(7, "zed"),
+            RankKey::new(0, ""),
+            RankKey::new(5, &long),
+            RankKey::new(5, &longer),
+            RankKey::new(u64::MAX, "max"),
+        ];
+        keys.sort();
+        let mut by_bytes = keys.clone();
+        by_bytes.sort_by_key(|key| bcs::to_bytes(key).unwrap());
+        assert_eq!(keys, by_bytes);
+        assert_eq!(keys[0], RankKey::new(u64::MAX, "max"));
+    }
 }
@@ -0,0 +1,283 @@
+// leaderboard/src/tier.rs
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hạng (Bronze -> Master), bậc trong hạng, chuỗi thăng hạng và bảo vệ rớt hạng, tính từ rating.
+
+use async_graphql::SimpleObject;
+use serde::{Deserialize, Serialize};
+
+/// Ngưỡng rating tối thiểu để vào một hạng.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct TierThreshold {
+    pub name: String,
+    pub min_rating: u64,
+}
+
+/// Cấu hình hạng, truyền vào lúc instantiate trong `LeaderboardConfig::tiers`.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TierConfig {
+    /// Các hạng theo thứ tự tăng dần; hạng cuối (cao nhất) không chia bậc.
+    pub tiers: Vec<TierThreshold>,
+    /// Số bậc trong mỗi hạng (bậc 1 là cao nhất).
+    pub divisions: u32,
+    /// Số trận của chuỗi thăng hạng khi lên hạng mới (0 = lên thẳng); thắng quá nửa là lên.
+    pub promotion_series_length: u32,
+    /// Số trận không bị rớt bậc ngay sau khi vừa lên.
+    pub demotion_protection_matches: u32,
+}
+
+impl Default for TierConfig {
+    fn default() -> Self {
+        let tier = |name: &str, min_rating| TierThreshold { name: name.to_string(), min_rating };
+        TierConfig {
+            tiers: vec![
+                tier("Bronze", 0),
+                tier("Silver", 1300),
+                tier("Gold", 1500),
+                tier("Platinum", 1700),
+                tier("Diamond", 1900),
+                tier("Master", 2100),
+            ],
+            divisions: 4,
+            promotion_series_length: 3,
+            demotion_protection_matches: 3,
+        }
+    }
+}
+
+/// Chuỗi thăng hạng đang diễn ra.
+#[derive(SimpleObject, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PromotionSeries {
+    pub target_tier: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub length: u32,
+}
+
+/// Trạng thái hạng đã lưu của một người chơi. `level` tăng dần từ 0 (bậc thấp nhất của hạng thấp nhất).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TierState {
+    pub level: u32,
+    pub promotion: Option<PromotionSeries>,
+    pub protection_left: u32,
+}
+
+impl TierConfig {
+    /// Sắp các hạng theo `min_rating` tăng dần và bỏ các hạng trùng ngưỡng (giữ hạng khai báo trước).
+    /// Gọi lúc instantiate: `natural_level` giả định ngưỡng tăng dần nghiêm ngặt.
+    pub fn normalize(&mut self) {
+        self.tiers.sort_by_key(|tier| tier.min_rating);
+        self.tiers.dedup_by_key(|tier| tier.min_rating);
+    }
+
+    fn divisions(&self) -> u32 {
+        self.divisions.max(1)
+    }
+
+    fn top_level(&self) -> u32 {
+        (self.tiers.len().max(1) as u32 - 1) * self.divisions()
+    }
+
+    /// Level tương ứng với rating, chưa tính chuỗi thăng hạng / bảo vệ.
+    pub fn natural_level(&self, rating: u64) -> u32 {
+        let Some(index) = self.tiers.iter().rposition(|tier| tier.min_rating <= rating) else {
+            return 0;
+        };
+        let divisions = self.divisions();
+        if index + 1 >= self.tiers.len() {
+            return self.top_level();
+        }
+        let min = self.tiers[index].min_rating;
+        let width = (self.tiers[index + 1].min_rating.saturating_sub(min) / divisions as u64).max(1);
+        let step = ((rating - min) / width).min(divisions as u64 - 1) as u32;
+        index as u32 * divisions + step
+    }
+
+    /// (tên hạng, bậc) của một level; bậc 0 = hạng cao nhất, không chia bậc.
+    pub fn tier_of(&self, level: u32) -> (String, u32) {
+        let level = level.min(self.top_level());
+        let divisions = self.divisions();
+        let index = (level / divisions) as usize;
+        let name = self.tiers.get(index).map(|tier| tier.name.clone()).unwrap_or_default();
+        if level == self.top_level() {
+            (name, 0)
+        } else {
+            (name, divisions - level % divisions)
+        }
+    }
+
+    /// Trạng thái ban đầu của người chơi mới.
+    pub fn initial_state(&self, rating: u64) -> TierState {
+        TierState { level: self.natural_level(rating), ..TierState::default() }
+    }
+
+    /// Cập nhật hạng sau một trận tính rating, với rating mới `rating`.
+    ///
+    /// - Lên bậc trong cùng hạng: ngay lập tức.
+    /// - Lên hạng mới: phải thắng chuỗi thăng hạng (nếu `promotion_series_length` > 0).
+    /// - Rớt bậc: từng bậc một, và không rớt khi còn lượt bảo vệ.
+    pub fn apply(&self, state: &mut TierState, rating: u64, won: bool) {
+        let target = self.natural_level(rating);
+        let divisions = self.divisions();
+        let protected = state.protection_left > 0;
+        state.protection_left = state.protection_left.saturating_sub(1);
+
+        if target <= state.level {
+            state.promotion = None;
+            if target < state.level && !protected && !won {
+                state.level -= 1;
+            }
+            return;
+        }
+
+        // Bậc cao nhất còn trong hạng hiện tại: lên thẳng không cần chuỗi.
+        let tier_top = (state.level / divisions * divisions + divisions - 1).min(self.top_level());
+        if state.level < tier_top {
+            state.level = target.min(tier_top);
+            state.protection_left = self.demotion_protection_matches;
+            return;
+        }
+
+        if self.promotion_series_length == 0 {
+            self.promote(state);
+            return;
+        }
+        match state.promotion.as_mut() {
+            None => {
+                // Trận vừa vượt ngưỡng mở chuỗi, chưa tính vào chuỗi.
+                state.promotion = Some(PromotionSeries {
+                    target_tier: self.tier_of(state.level + 1).0,
+                    wins: 0,
+                    losses: 0,
+                    length: self.promotion_series_length,
+                });
+            }
+            Some(series) => {
+                if won {
+                    series.wins += 1;
+                } else {
+                    series.losses += 1;
+                }
+                let needed = series.length / 2 + 1;
+                if series.wins >= needed {
+                    self.promote(state);
+                } else if series.losses >= needed {
+                    state.promotion = None;
+                }
+            }
+        }
+    }
+
+    fn promote(&self, state: &mut TierState) {
+        state.level = (state.level + 1).min(self.top_level());
+        state.promotion = None;
+        state.protection_left = self.demotion_protection_matches;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TierConfig, TierState, TierThreshold};
+
+    /// Người chơi ở Bronze 1 (bậc cao nhất của Bronze), ngay dưới ngưỡng Silver.
+    fn bronze_one(config: &TierConfig) -> TierState {
+        let state = config.initial_state(1299);
+        assert_eq!(config.tier_of(state.level), ("Bronze".to_string(), 1));
+        state
+    }
+
+    #[test]
+    fn misordered_tiers_are_sorted_and_deduplicated() {
+        let tier = |name: &str, min_rating| TierThreshold { name: name.to_string(), min_rating };
+        let mut config = TierConfig {
+            tiers: vec![tier("Gold", 1500), tier("Bronze", 0), tier("Silver", 1300), tier("Steel", 1300)],
+            ..TierConfig::default()
+        };
+        config.normalize();
+        let names: Vec<_> = config.tiers.iter().map(|tier| tier.name.as_str()).collect();
+        assert_eq!(names, ["Bronze", "Silver", "Gold"]);
+        assert_eq!(config.tier_of(config.natural_level(1400)), ("Silver".to_string(), 2));
+        assert_eq!(config.tier_of(config.natural_level(1600)), ("Gold".to_string(), 0));
+    }
+
+    #[test]
+    fn divisions_split_each_tier_evenly() {
+        let config = TierConfig::default();
+        assert_eq!(config.tier_of(config.natural_level(0)), ("Bronze".to_string(), 4));
+        assert_eq!(config.tier_of(config.natural_level(1300)), ("Silver".to_string(), 4));
+        assert_eq!(config.tier_of(config.natural_level(1349)), ("Silver".to_string(), 4));
+        assert_eq!(config.tier_of(config.natural_level(1350)), ("Silver".to_string(), 3));
+        assert_eq!(config.tier_of(config.natural_level(1499)), ("Silver".to_string(), 1));
+    }
+
+    #[test]
+    fn top_tier_has_no_divisions() {
+        let config = TierConfig::default();
+        let top = config.natural_level(2100);
+        assert_eq!(config.tier_of(top), ("Master".to_string(), 0));
+        assert_eq!(config.natural_level(5000), top);
+        assert_eq!(config.tier_of(top + 10), ("Master".to_string(), 0));
+    }
+
+    #[test]
+    fn promotion_within_a_tier_is_immediate() {
+        let config = TierConfig::default();
+        let mut state = config.initial_state(1300);
+        config.apply(&mut state, 1450, true);
+        assert_eq!(config.tier_of(state.level), ("Silver".to_string(), 1));
+        assert!(state.promotion.is_none());
+        assert_eq!(state.protection_left, config.demotion_protection_matches);
+    }
+
+    #[test]
+    fn crossing_into_a_new_tier_opens_a_series() {
+        let config = TierConfig::default();
+        let mut state = bronze_one(&config);
+        config.apply(&mut state, 1320, true);
+        assert_eq!(config.tier_of(state.level), ("Bronze".to_string(), 1));
+        let series = state.promotion.clone().expect("series should be open");
+        assert_eq!(series.target_tier, "Silver");
+        assert_eq!((series.wins, series.losses, series.length), (0, 0, 3));
+    }
+
+    #[test]
+    fn winning_the_series_promotes() {
+        let config = TierConfig::default();
+        let mut state = bronze_one(&config);
+        config.apply(&mut state, 1320, true);
+        config.apply(&mut state, 1330, true);
+        assert!(state.promotion.is_some());
+        config.apply(&mut state, 1340, true);
+        assert_eq!(config.tier_of(state.level), ("Silver".to_string(), 4));
+        assert!(state.promotion.is_none());
+        assert_eq!(state.protection_left, config.demotion_protection_matches);
+    }
+
+    #[test]
+    fn losing_the_series_stays_in_the_tier() {
+        let config = TierConfig::default();
+        let mut state = bronze_one(&config);
+        config.apply(&mut state, 1320, true);
+        config.apply(&mut state, 1310, false);
+        assert_eq!(state.promotion.as_ref().map(|series| series.losses), Some(1));
+        config.apply(&mut state, 1305, false);
+        assert_eq!(config.tier_of(state.level), ("Bronze".to_string(), 1));
+        assert!(state.promotion.is_none());
+    }
+
+    #[test]
+    fn protection_blocks_demotion_until_used_up() {
+        let config = TierConfig::default();
+        let mut state = TierState { level: config.natural_level(1300), promotion: None, protection_left: 3 };
+        for left in [2, 1, 0] {
+            config.apply(&mut state, 1000, false);
+            assert_eq!(config.tier_of(state.level), ("Silver".to_string(), 4));
+            assert_eq!(state.protection_left, left);
+        }
+        // Hết bảo vệ: rớt từng bậc một dù rating thấp hơn nhiều.
+        config.apply(&mut state, 0, false);
+        assert_eq!(config.tier_of(state.level), ("Bronze".to_string(), 1));
+    }
+}
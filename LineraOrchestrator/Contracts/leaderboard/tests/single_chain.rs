@@ -1,35 +1,864 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-//! Integration tests for the Counter application.
+//! Integration tests for the Leaderboard application.
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use linera_sdk::test::{QueryOutcome, TestValidator};
+use assert_matches::assert_matches;
+use leaderboard::{
+    integrity::IntegrityConfig, rating::PlacementConfig, BoardConfig, LeaderboardAbi, LeaderboardConfig,
+    LeaderboardResponse, MatchOutcome, Operation, Parameters, SCORE_HISTORY_LEN,
+};
+use linera_sdk::{
+    bcs,
+    linera_base_types::{AccountOwner, ApplicationId, ModuleId, TimeDelta},
+    test::{ActiveChain, QueryOutcome, TestValidator},
+};
 
-// ANCHOR: counter_integration_test
+/// Các trường của `playerStats` mà huỷ / sửa trận phải khôi phục.
+const PLAYER_FIELDS: &str = "totalMatches totalWins totalLosses score rating currentStreak bestWinStreak recentForm";
+
+/// Tạo leaderboard mà owner của chain vừa được ghi điểm vừa là admin (Parameters::admins rỗng).
+async fn create_leaderboard(
+    chain: &mut ActiveChain,
+    module_id: ModuleId<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>,
+    config: Option<LeaderboardConfig>,
+) -> ApplicationId<LeaderboardAbi> {
+    let parameters = Parameters {
+        authorized_owners: vec![AccountOwner::from(chain.public_key())],
+        ..Parameters::default()
+    };
+    chain.create_application(module_id, parameters, config, vec![]).await
+}
+
+/// Hai nửa `RecordScore` của một trận.
+fn record(match_id: &str, winner: &str, loser: &str) -> [Operation; 2] {
+    [(winner, true), (loser, false)].map(|(user_id, is_winner)| Operation::RecordScore {
+        user_id: user_id.to_string(),
+        is_winner,
+        match_id: match_id.to_string(),
+        match_type: "Ranked".to_string(),
+        map_name: "Arena".to_string(),
+    })
+}
+
+/// Cả trận `winner` thắng `loser` (Ranked, Arena, đủ dài để không bị gắn cờ), không hero, không bảng có tên.
+fn outcome(match_id: &str, winner: &str, loser: &str) -> MatchOutcome {
+    MatchOutcome {
+        match_id: match_id.to_string(),
+        winner: winner.to_string(),
+        loser: loser.to_string(),
+        match_type: "Ranked".to_string(),
+        map_name: "Arena".to_string(),
+        winner_score: 3,
+        loser_score: 1,
+        duration_seconds: 300,
+        winner_hero: None,
+        loser_hero: None,
+        board_id: None,
+    }
+}
+
+async fn submit(chain: &ActiveChain, application_id: ApplicationId<LeaderboardAbi>, operations: Vec<Operation>) {
+    chain
+        .add_block(|block| {
+            for operation in operations {
+                block.with_operation(application_id, operation);
+            }
+        })
+        .await;
+}
+
+/// Ghi các operation trong một block, trả về phản hồi (BCS) của từng operation theo thứ tự.
+async fn responses(
+    chain: &ActiveChain,
+    application_id: ApplicationId<LeaderboardAbi>,
+    operations: Vec<Operation>,
+) -> Vec<LeaderboardResponse> {
+    let certificate = chain
+        .add_block(|block| {
+            for operation in operations {
+                block.with_operation(application_id, operation);
+            }
+        })
+        .await;
+    certificate
+        .inner()
+        .block()
+        .body
+        .operation_results
+        .iter()
+        .map(|result| bcs::from_bytes(&result.0).expect("Failed to decode an operation response"))
+        .collect()
+}
+
+/// Thống kê của alice, bob và thành tích đối đầu giữa hai người.
+async fn alice_and_bob(chain: &ActiveChain, application_id: ApplicationId<LeaderboardAbi>) -> serde_json::Value {
+    let query = format!(
+        "query {{ alice: playerStats(userId: \"alice\") {{ {PLAYER_FIELDS} }} \
+         bob: playerStats(userId: \"bob\") {{ {PLAYER_FIELDS} }} \
+         headToHead(a: \"alice\", b: \"bob\") {{ wins losses totalMatches }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    response
+}
+
+/// Ghi cùng một kết quả (match_id, user_id) nhiều lần: chỉ lần đầu được tính,
+/// các lần sau nằm trong `duplicateResults` và không làm thay đổi thống kê.
 #[tokio::test(flavor = "multi_thread")]
-async fn single_chain_test() {
+async fn replayed_results_leave_stats_unchanged() {
     let (validator, module_id) =
-        TestValidator::with_current_module::<xfighter::CounterAbi, (), u64>().await;
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
     let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    let [winner, loser] = record("m1", "alice", "bob");
+    submit(&chain, application_id, vec![winner.clone(), loser.clone()]).await;
 
-    let initial_state = 42u64;
-    let application_id = chain
-        .create_application(module_id, (), initial_state, vec![])
+    let query = "query { leaderboard { userId totalMatches totalWins totalLosses score rating } }";
+    let QueryOutcome { response: before, .. } = chain.graphql_query(application_id, query).await;
+
+    // Gửi lại cả hai nửa, trong cùng block và ở block sau.
+    submit(&chain, application_id, vec![winner.clone(), winner]).await;
+    submit(&chain, application_id, vec![loser]).await;
+
+    let QueryOutcome { response: after, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(before, after);
+    assert_eq!(after["leaderboard"][0]["userId"], "alice");
+    assert_eq!(after["leaderboard"][0]["totalMatches"].as_u64(), Some(1));
+    assert_eq!(after["leaderboard"][1]["totalLosses"].as_u64(), Some(1));
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { duplicateResults { matchId userId source } }")
         .await;
+    let duplicates = response["duplicateResults"].as_array().expect("Failed to get duplicates");
+    assert_eq!(duplicates.len(), 3);
+    assert!(duplicates.iter().all(|d| d["matchId"] == "m1" && d["source"] == "operation"));
+}
 
-    let increment = 15u64;
-    chain
-        .add_block(|block| {
-            block.with_operation(application_id, increment);
+/// Message dạng cũ (không có loại trận / map) được chuyển tiếp thành `RecordScore` với hai trường rỗng:
+/// vẫn được ghép và tính vào bảng chính, nhưng không tạo bảng phụ theo loại trận / map.
+#[tokio::test(flavor = "multi_thread")]
+async fn legacy_shaped_results_count_on_the_main_board_only() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    let legacy = record("m1", "alice", "bob").map(|operation| match operation {
+        Operation::RecordScore { user_id, is_winner, match_id, .. } => Operation::RecordScore {
+            user_id,
+            is_winner,
+            match_id,
+            match_type: String::new(),
+            map_name: String::new(),
+        },
+        operation => operation,
+    });
+    submit(&chain, application_id, legacy.to_vec()).await;
+
+    let response = alice_and_bob(&chain, application_id).await;
+    assert_eq!(response["alice"]["totalWins"].as_u64(), Some(1));
+    assert_eq!(response["bob"]["totalLosses"].as_u64(), Some(1));
+    assert_eq!(response["headToHead"]["totalMatches"].as_u64(), Some(1));
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, "query { boards }").await;
+    assert_eq!(response["boards"].as_array().map(Vec::len), Some(0));
+}
+
+/// match_id / user_id chứa ':' không được trùng khoá idempotency của nhau.
+#[tokio::test(flavor = "multi_thread")]
+async fn ids_containing_colons_do_not_collide() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    submit(&chain, application_id, record("a:b", "c", "x").to_vec()).await;
+    submit(&chain, application_id, record("a", "b:c", "y").to_vec()).await;
+
+    let query = "query { c: playerStats(userId: \"c\") { totalWins } \
+        bc: playerStats(userId: \"b:c\") { totalWins } duplicateResults { matchId } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response["c"]["totalWins"].as_u64(), Some(1));
+    assert_eq!(response["bc"]["totalWins"].as_u64(), Some(1));
+    assert_eq!(response["duplicateResults"].as_array().map(Vec::len), Some(0));
+}
+
+/// `VoidMatch` đưa bộ đếm, rating, chuỗi / phong độ và đối đầu về đúng trạng thái trước trận.
+#[tokio::test(flavor = "multi_thread")]
+async fn void_match_restores_player_state() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    submit(&chain, application_id, record("m1", "alice", "bob").to_vec()).await;
+    let before = alice_and_bob(&chain, application_id).await;
+
+    submit(&chain, application_id, record("m2", "bob", "alice").to_vec()).await;
+    assert_ne!(alice_and_bob(&chain, application_id).await, before);
+
+    submit(&chain, application_id, vec![Operation::VoidMatch { match_id: "m2".to_string() }]).await;
+    let after = alice_and_bob(&chain, application_id).await;
+    assert_eq!(after, before);
+    assert_eq!(after["alice"]["currentStreak"].as_i64(), Some(1));
+    assert_eq!(after["headToHead"]["wins"].as_u64(), Some(1));
+    assert_eq!(after["headToHead"]["losses"].as_u64(), Some(0));
+}
+
+/// `CorrectMatch` cho cùng trạng thái như khi trận được ghi đúng ngay từ đầu.
+#[tokio::test(flavor = "multi_thread")]
+async fn correct_match_matches_a_correct_recording() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let corrected_id = create_leaderboard(&mut chain, module_id, None).await;
+    let expected_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    submit(&chain, corrected_id, record("m1", "alice", "bob").to_vec()).await;
+    submit(&chain, corrected_id, record("m2", "alice", "bob").to_vec()).await;
+    let correction = Operation::CorrectMatch {
+        match_id: "m2".to_string(),
+        winner: "bob".to_string(),
+        loser: "alice".to_string(),
+    };
+    submit(&chain, corrected_id, vec![correction]).await;
+
+    submit(&chain, expected_id, record("m1", "alice", "bob").to_vec()).await;
+    submit(&chain, expected_id, record("m2", "bob", "alice").to_vec()).await;
+
+    let corrected = alice_and_bob(&chain, corrected_id).await;
+    assert_eq!(corrected, alice_and_bob(&chain, expected_id).await);
+    assert_eq!(corrected["bob"]["recentForm"], "LW");
+    assert_eq!(corrected["headToHead"]["losses"].as_u64(), Some(1));
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(corrected_id, "query { corrections { matchId action previousWinner newWinner } }")
+        .await;
+    assert_eq!(response["corrections"][0]["matchId"], "m2");
+    assert_eq!(response["corrections"][0]["previousWinner"], "alice");
+    assert_eq!(response["corrections"][0]["newWinner"], "bob");
+}
+
+/// Gộp hai người chơi cùng gặp một đối thủ: chuỗi / phong độ theo thứ tự thời gian của các trận,
+/// đối đầu với đối thủ chung được cộng dồn.
+#[tokio::test(flavor = "multi_thread")]
+async fn merging_players_with_a_shared_opponent_keeps_match_order() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    for (match_id, winner, loser) in [("m1", "alice", "carol"), ("m2", "carol", "bob"), ("m3", "alice", "carol")] {
+        validator.clock().add(TimeDelta::from_secs(60));
+        submit(&chain, application_id, record(match_id, winner, loser).to_vec()).await;
+    }
+    let merge = Operation::MergePlayers { from: "bob".to_string(), to: "alice".to_string() };
+    submit(&chain, application_id, vec![merge]).await;
+
+    let query = format!(
+        "query {{ playerStats(userId: \"alice\") {{ {PLAYER_FIELDS} }} \
+         headToHead(a: \"alice\", b: \"carol\") {{ wins losses }} \
+         rivals(userId: \"carol\") {{ opponent }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    let alice = &response["playerStats"];
+    assert_eq!(alice["totalMatches"].as_u64(), Some(3));
+    assert_eq!(alice["recentForm"], "WLW");
+    assert_eq!(alice["currentStreak"].as_i64(), Some(1));
+    assert_eq!(alice["bestWinStreak"].as_u64(), Some(1));
+    assert_eq!(response["headToHead"]["wins"].as_u64(), Some(2));
+    assert_eq!(response["headToHead"]["losses"].as_u64(), Some(1));
+    let rivals = response["rivals"].as_array().expect("Failed to get rivals");
+    assert_eq!(rivals.len(), 1);
+    assert_eq!(rivals[0]["opponent"], "alice");
+}
+
+/// Người chơi provisional không có thứ hạng và không chiếm thứ hạng trong `rankOf`, `around`,
+/// `groupLeaderboard`, trừ khi `includeProvisional`.
+#[tokio::test(flavor = "multi_thread")]
+async fn provisional_players_are_skipped_in_rank_lookups() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let config = LeaderboardConfig {
+        placement: PlacementConfig { matches: 2, ..PlacementConfig::default() },
+        ..LeaderboardConfig::default()
+    };
+    let application_id = create_leaderboard(&mut chain, module_id, Some(config)).await;
+
+    // alice, bob đã đủ 2 trận placement; carol, dave mới có 1 trận.
+    submit(&chain, application_id, record("m1", "alice", "bob").to_vec()).await;
+    submit(&chain, application_id, record("m2", "alice", "bob").to_vec()).await;
+    submit(&chain, application_id, record("m3", "carol", "dave").to_vec()).await;
+
+    let query = "query { \
+        carol: rankOf(userId: \"carol\") \
+        carolAll: rankOf(userId: \"carol\", includeProvisional: true) \
+        bob: rankOf(userId: \"bob\") \
+        bobAll: rankOf(userId: \"bob\", includeProvisional: true) \
+        around(userId: \"alice\", radius: 1) { rank entry { userId } } \
+        groupLeaderboard(userIds: [\"carol\", \"bob\", \"alice\"]) { rank entry { userId } } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert!(response["carol"].is_null());
+    assert_eq!(response["carolAll"].as_u64(), Some(2));
+    assert_eq!(response["bob"].as_u64(), Some(2));
+    assert_eq!(response["bobAll"].as_u64(), Some(3));
+
+    let ranked = |field: &str| {
+        response[field]
+            .as_array()
+            .expect("Failed to get ranked entries")
+            .iter()
+            .map(|ranked| (ranked["rank"].as_u64().unwrap(), ranked["entry"]["userId"].as_str().unwrap().to_string()))
+            .collect::<Vec<_>>()
+    };
+    let expected = vec![(1, "alice".to_string()), (2, "bob".to_string())];
+    assert_eq!(ranked("around"), expected);
+    assert_eq!(ranked("groupLeaderboard"), expected);
+}
+
+/// `groupLeaderboard` trả về thành viên theo thứ hạng toàn bảng, gộp `userIds` với nhóm đã lưu,
+/// bỏ người trùng và người chưa được xếp hạng.
+#[tokio::test(flavor = "multi_thread")]
+async fn group_standings_follow_the_global_ranking() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    // alice 3-0, bob 2-1, carol 1-2, dave 0-3.
+    let matches = [
+        ("alice", "bob"),
+        ("alice", "carol"),
+        ("alice", "dave"),
+        ("bob", "carol"),
+        ("bob", "dave"),
+        ("carol", "dave"),
+    ];
+    for (i, (winner, loser)) in matches.into_iter().enumerate() {
+        submit(&chain, application_id, record(&format!("m{i}"), winner, loser).to_vec()).await;
+    }
+    let save_group = Operation::SaveGroup {
+        group_id: "squad".to_string(),
+        name: "Squad".to_string(),
+        kind: "team".to_string(),
+        members: vec!["dave".to_string(), "bob".to_string(), "ghost".to_string()],
+    };
+    submit(&chain, application_id, vec![save_group]).await;
+
+    let query =
+        "query { groupLeaderboard(userIds: [\"bob\", \"alice\"], groupId: \"squad\") { rank entry { userId } } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    let standings = response["groupLeaderboard"]
+        .as_array()
+        .expect("Failed to get group standings")
+        .iter()
+        .map(|ranked| (ranked["rank"].as_u64().unwrap(), ranked["entry"]["userId"].as_str().unwrap().to_string()))
+        .collect::<Vec<_>>();
+    let expected = vec![(1, "alice".to_string()), (2, "bob".to_string()), (4, "dave".to_string())];
+    assert_eq!(standings, expected);
+}
+
+/// Sau khi đổi tên, mọi truy vấn theo người chơi qua tên cũ trả về đúng dữ liệu của tên mới.
+#[tokio::test(flavor = "multi_thread")]
+async fn per_user_queries_follow_aliases() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    submit(&chain, application_id, record("m1", "alice", "bob").to_vec()).await;
+    submit(&chain, application_id, record("m2", "alice", "carol").to_vec()).await;
+    let rename = Operation::RenamePlayer { from: "alice".to_string(), to: "ace".to_string() };
+    submit(&chain, application_id, vec![rename]).await;
+
+    let fields = |user_id: &str| {
+        format!(
+            "score(userId: \"{user_id}\") \
+             rating(userId: \"{user_id}\") \
+             rankOf(userId: \"{user_id}\") \
+             lastPlayed(userId: \"{user_id}\") \
+             around(userId: \"{user_id}\", radius: 1) {{ rank entry {{ userId }} }} \
+             headToHead(a: \"{user_id}\", b: \"bob\") {{ userId opponent wins losses totalMatches }} \
+             rivals(userId: \"{user_id}\") {{ userId opponent wins }} \
+             playerMapStats(userId: \"{user_id}\") {{ key matches wins }} \
+             playerHeroStats(userId: \"{user_id}\") {{ key matches wins }} \
+             playerSeasonHistory(userId: \"{user_id}\") {{ seasonId }}"
+        )
+    };
+    let QueryOutcome { response: current, .. } =
+        chain.graphql_query(application_id, format!("query {{ {} }}", fields("ace"))).await;
+    let QueryOutcome { response: aliased, .. } =
+        chain.graphql_query(application_id, format!("query {{ {} }}", fields("alice"))).await;
+    assert_eq!(aliased, current);
+    assert_eq!(aliased["rankOf"].as_u64(), Some(1));
+    assert_eq!(aliased["headToHead"]["userId"], "ace");
+    assert_eq!(aliased["headToHead"]["wins"].as_u64(), Some(1));
+    assert_eq!(aliased["rivals"].as_array().map(Vec::len), Some(2));
+    assert_eq!(aliased["playerMapStats"][0]["key"], "Arena");
+    assert_eq!(aliased["playerMapStats"][0]["wins"].as_u64(), Some(2));
+}
+
+/// Trận quá ngắn bị gắn cờ và loại khỏi bảng xếp hạng khi `exclude_flagged`; `ClearFlag` ghi lại trận.
+#[tokio::test(flavor = "multi_thread")]
+async fn flagged_match_is_excluded_until_cleared() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let config = LeaderboardConfig {
+        integrity: IntegrityConfig { min_duration_seconds: 30, exclude_flagged: true, ..IntegrityConfig::default() },
+        ..LeaderboardConfig::default()
+    };
+    let application_id = create_leaderboard(&mut chain, module_id, Some(config)).await;
+
+    let outcome = MatchOutcome {
+        match_id: "m1".to_string(),
+        winner: "alice".to_string(),
+        loser: "bob".to_string(),
+        match_type: "Ranked".to_string(),
+        map_name: "Arena".to_string(),
+        winner_score: 3,
+        loser_score: 0,
+        duration_seconds: 5,
+        winner_hero: None,
+        loser_hero: None,
+        board_id: None,
+    };
+    submit(&chain, application_id, vec![Operation::RecordMatch(outcome)]).await;
+
+    let query = "query { flaggedMatch(matchId: \"m1\") { reasons excluded cleared } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response["flaggedMatch"]["reasons"][0], "short_duration");
+    assert_eq!(response["flaggedMatch"]["excluded"], true);
+    let excluded = alice_and_bob(&chain, application_id).await;
+    assert_eq!(excluded["alice"]["totalWins"].as_u64(), Some(0));
+    assert_eq!(excluded["bob"]["totalLosses"].as_u64(), Some(0));
+    assert_eq!(excluded["headToHead"]["totalMatches"].as_u64(), Some(0));
+
+    submit(&chain, application_id, vec![Operation::ClearFlag { match_id: "m1".to_string() }]).await;
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response["flaggedMatch"]["excluded"], false);
+    assert_eq!(response["flaggedMatch"]["cleared"], true);
+    let restored = alice_and_bob(&chain, application_id).await;
+    assert_eq!(restored["alice"]["totalWins"].as_u64(), Some(1));
+    assert_eq!(restored["alice"]["recentForm"], "W");
+    assert_eq!(restored["bob"]["totalLosses"].as_u64(), Some(1));
+    assert!(restored["alice"]["rating"].as_u64() > restored["bob"]["rating"].as_u64());
+    assert_eq!(restored["headToHead"]["wins"].as_u64(), Some(1));
+}
+
+/// `CloseSeason` lưu bảng xếp hạng cuối mùa vào `seasonStandings` / `playerSeasonHistory`,
+/// reset bộ đếm và chuỗi / phong độ cho mùa mới nhưng giữ rating.
+#[tokio::test(flavor = "multi_thread")]
+async fn closing_a_season_archives_standings_and_resets_counters() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    for (match_id, winner, loser) in [("m1", "alice", "bob"), ("m2", "alice", "carol"), ("m3", "bob", "carol")] {
+        submit(&chain, application_id, record(match_id, winner, loser).to_vec()).await;
+    }
+    let before = alice_and_bob(&chain, application_id).await;
+    submit(&chain, application_id, vec![Operation::CloseSeason]).await;
+
+    let query = "query { currentSeason seasons { seasonId playerCount champion } \
+        seasonStandings(seasonId: 1) { userId totalWins currentStreak } \
+        top: seasonStandings(seasonId: 1, limit: 1) { userId } \
+        playerSeasonHistory(userId: \"bob\") { seasonId rank entry { totalWins totalLosses recentForm } } \
+        leaderboard { userId } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response["currentSeason"].as_u64(), Some(2));
+    assert_eq!(response["seasons"], serde_json::json!([{ "seasonId": 1, "playerCount": 3, "champion": "alice" }]));
+    assert_eq!(
+        response["seasonStandings"],
+        serde_json::json!([
+            { "userId": "alice", "totalWins": 2, "currentStreak": 2 },
+            { "userId": "bob", "totalWins": 1, "currentStreak": 1 },
+            { "userId": "carol", "totalWins": 0, "currentStreak": -2 },
+        ])
+    );
+    assert_eq!(response["top"], serde_json::json!([{ "userId": "alice" }]));
+    assert_eq!(
+        response["playerSeasonHistory"],
+        serde_json::json!([
+            { "seasonId": 1, "rank": 2, "entry": { "totalWins": 1, "totalLosses": 1, "recentForm": "LW" } },
+        ])
+    );
+    assert_eq!(response["leaderboard"], serde_json::json!([]));
+
+    let after = alice_and_bob(&chain, application_id).await;
+    assert_eq!(after["alice"]["rating"], before["alice"]["rating"]);
+    assert_eq!(after["alice"]["totalMatches"].as_u64(), Some(0));
+    assert_eq!(after["alice"]["currentStreak"].as_i64(), Some(0));
+    assert_eq!(after["alice"]["bestWinStreak"].as_u64(), Some(0));
+    assert_eq!(after["alice"]["recentForm"], "");
+
+    // Mùa mới bắt đầu chuỗi từ đầu.
+    submit(&chain, application_id, record("m4", "bob", "alice").to_vec()).await;
+    let response = alice_and_bob(&chain, application_id).await;
+    assert_eq!(response["alice"]["totalLosses"].as_u64(), Some(1));
+    assert_eq!(response["alice"]["currentStreak"].as_i64(), Some(-1));
+    assert_eq!(response["alice"]["recentForm"], "L");
+}
+
+/// Trận có `board_id` được cộng vào bảng có tên theo `BoardConfig` của bảng; loại trận không được cấu hình,
+/// bảng không tồn tại hoặc đã lưu trữ thì bị bỏ qua. Bảng có tên giữ nguyên qua `CloseSeason`.
+#[tokio::test(flavor = "multi_thread")]
+async fn results_with_a_board_id_feed_the_named_board() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    let create = Operation::CreateBoard {
+        board_id: "eu".to_string(),
+        name: "Europe".to_string(),
+        config: BoardConfig { match_types: vec!["Ranked".to_string()], points_per_win: 3, points_per_loss: 1 },
+    };
+    submit(&chain, application_id, vec![create]).await;
+
+    let on_board = |match_id: &str, winner: &str, loser: &str, match_type: &str, board_id: &str| {
+        Operation::RecordMatch(MatchOutcome {
+            match_type: match_type.to_string(),
+            board_id: Some(board_id.to_string()),
+            ..outcome(match_id, winner, loser)
+        })
+    };
+    let results = vec![
+        on_board("m1", "alice", "bob", "Ranked", "eu"),
+        on_board("m2", "alice", "carol", "Ranked", "eu"),
+        on_board("m3", "bob", "alice", "Ranked", "eu"),
+        on_board("m4", "carol", "alice", "Normal", "eu"),
+        on_board("m5", "carol", "alice", "Ranked", "na"),
+    ];
+    submit(&chain, application_id, results).await;
+    submit(&chain, application_id, vec![Operation::CloseSeason]).await;
+
+    let query = "query { leaderboard(board: { boardId: \"eu\" }) { userId totalWins totalLosses score } }";
+    let QueryOutcome { response: before_archive, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(
+        before_archive["leaderboard"],
+        serde_json::json!([
+            { "userId": "alice", "totalWins": 2, "totalLosses": 1, "score": 5 },
+            { "userId": "bob", "totalWins": 1, "totalLosses": 1, "score": 2 },
+            { "userId": "carol", "totalWins": 0, "totalLosses": 1, "score": 0 },
+        ])
+    );
+
+    submit(&chain, application_id, vec![Operation::ArchiveBoard { board_id: "eu".to_string() }]).await;
+    submit(&chain, application_id, vec![on_board("m6", "carol", "alice", "Ranked", "eu")]).await;
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response, before_archive);
+    let query = "query { boardInfo(boardId: \"eu\") { archived } na: boardInfo(boardId: \"na\") { archived } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response["boardInfo"]["archived"], true);
+    assert!(response["na"].is_null());
+}
+
+/// Mỗi loại trận / map có bảng phụ riêng xếp theo số trận thắng; chỉ loại trận trong
+/// `headline_match_types` được tính vào bảng chính.
+#[tokio::test(flavor = "multi_thread")]
+async fn match_types_and_maps_have_their_own_boards() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let config = LeaderboardConfig { headline_match_types: vec!["Ranked".to_string()], ..LeaderboardConfig::default() };
+    let application_id = create_leaderboard(&mut chain, module_id, Some(config)).await;
+
+    let typed = |match_id: &str, winner: &str, loser: &str, match_type: &str, map_name: &str| {
+        record(match_id, winner, loser).map(|operation| match operation {
+            Operation::RecordScore { user_id, is_winner, match_id, .. } => Operation::RecordScore {
+                user_id,
+                is_winner,
+                match_id,
+                match_type: match_type.to_string(),
+                map_name: map_name.to_string(),
+            },
+            operation => operation,
         })
+    };
+    submit(&chain, application_id, typed("m1", "alice", "bob", "Ranked", "Arena").to_vec()).await;
+    submit(&chain, application_id, typed("m2", "bob", "alice", "Normal", "Dojo").to_vec()).await;
+    submit(&chain, application_id, typed("m3", "bob", "carol", "Normal", "Arena").to_vec()).await;
+
+    let query = "query { boards \
+        headline: leaderboard { userId totalWins totalLosses } \
+        normal: leaderboard(board: { matchType: \"Normal\" }) { userId totalWins totalLosses score } \
+        arena: leaderboard(board: { mapName: \"Arena\" }) { userId score } \
+        normalDojo: leaderboard(board: { matchType: \"Normal\", mapName: \"Dojo\" }) { userId score } \
+        missing: leaderboard(board: { matchType: \"Casual\" }) { userId } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+
+    let mut boards = response["boards"]
+        .as_array()
+        .expect("Failed to get boards")
+        .iter()
+        .map(|board| board.as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    boards.sort();
+    let expected = [
+        "map:Arena",
+        "map:Dojo",
+        "type:Normal",
+        "type:Normal/map:Arena",
+        "type:Normal/map:Dojo",
+        "type:Ranked",
+        "type:Ranked/map:Arena",
+    ];
+    assert_eq!(boards, expected);
+
+    assert_eq!(
+        response["headline"],
+        serde_json::json!([
+            { "userId": "alice", "totalWins": 1, "totalLosses": 0 },
+            { "userId": "bob", "totalWins": 0, "totalLosses": 1 },
+        ])
+    );
+    assert_eq!(
+        response["normal"],
+        serde_json::json!([
+            { "userId": "bob", "totalWins": 2, "totalLosses": 0, "score": 2 },
+            { "userId": "alice", "totalWins": 0, "totalLosses": 1, "score": 0 },
+            { "userId": "carol", "totalWins": 0, "totalLosses": 1, "score": 0 },
+        ])
+    );
+    assert_eq!(
+        response["arena"],
+        serde_json::json!([
+            { "userId": "alice", "score": 1 },
+            { "userId": "bob", "score": 1 },
+            { "userId": "carol", "score": 0 },
+        ])
+    );
+    assert_eq!(
+        response["normalDojo"],
+        serde_json::json!([{ "userId": "bob", "score": 1 }, { "userId": "alice", "score": 0 }])
+    );
+    assert_eq!(response["missing"], serde_json::json!([]));
+}
+
+/// Chuỗi hiện tại (dương khi thắng, âm khi thua), chuỗi thắng dài nhất và phong độ 10 trận gần nhất,
+/// trên `playerStats` lẫn các mục của `leaderboard`.
+#[tokio::test(flavor = "multi_thread")]
+async fn streaks_and_recent_form_follow_the_results() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    // alice: L, W x5, L x2, W x3 (11 trận, phong độ chỉ giữ 10 trận cuối).
+    let alice_won = [false, true, true, true, true, true, false, false, true, true, true];
+    for (i, won) in alice_won.into_iter().enumerate() {
+        let (winner, loser) = if won { ("alice", "bob") } else { ("bob", "alice") };
+        submit(&chain, application_id, record(&format!("m{i}"), winner, loser).to_vec()).await;
+    }
+
+    let response = alice_and_bob(&chain, application_id).await;
+    assert_eq!(response["alice"]["currentStreak"].as_i64(), Some(3));
+    assert_eq!(response["alice"]["bestWinStreak"].as_u64(), Some(5));
+    assert_eq!(response["alice"]["recentForm"], "WWWWWLLWWW");
+    assert_eq!(response["bob"]["currentStreak"].as_i64(), Some(-3));
+    assert_eq!(response["bob"]["bestWinStreak"].as_u64(), Some(2));
+    assert_eq!(response["bob"]["recentForm"], "LLLLLWWLLL");
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { leaderboard { userId currentStreak bestWinStreak recentForm } }")
         .await;
+    assert_eq!(
+        response["leaderboard"],
+        serde_json::json!([
+            { "userId": "alice", "currentStreak": 3, "bestWinStreak": 5, "recentForm": "WWWWWLLWWW" },
+            { "userId": "bob", "currentStreak": -3, "bestWinStreak": 2, "recentForm": "LLLLLWWLLL" },
+        ])
+    );
+}
+
+/// Đối đầu chỉ được ghi khi đã nhận đủ hai nửa của trận; `headToHead` nhìn từ phía `a`,
+/// `rivals` xếp đối thủ theo số trận đã gặp.
+#[tokio::test(flavor = "multi_thread")]
+async fn head_to_head_and_rivals_follow_paired_results() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    let matches = [("m1", "alice", "bob"), ("m2", "bob", "alice"), ("m3", "alice", "bob"), ("m4", "carol", "alice")];
+    for (match_id, winner, loser) in matches {
+        submit(&chain, application_id, record(match_id, winner, loser).to_vec()).await;
+    }
+    // Chỉ có nửa của người thắng: chưa tính vào đối đầu.
+    let [half, _] = record("m5", "dave", "alice");
+    submit(&chain, application_id, vec![half]).await;
+
+    let query = "query { \
+        ab: headToHead(a: \"alice\", b: \"bob\") { userId opponent wins losses totalMatches lastMatchId } \
+        ba: headToHead(a: \"bob\", b: \"alice\") { userId opponent wins losses totalMatches } \
+        ad: headToHead(a: \"alice\", b: \"dave\") { wins losses totalMatches } \
+        rivals(userId: \"alice\") { opponent wins losses totalMatches } \
+        top: rivals(userId: \"alice\", limit: 1) { opponent } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(
+        response["ab"],
+        serde_json::json!({
+            "userId": "alice", "opponent": "bob", "wins": 2, "losses": 1, "totalMatches": 3, "lastMatchId": "m3"
+        })
+    );
+    assert_eq!(
+        response["ba"],
+        serde_json::json!({ "userId": "bob", "opponent": "alice", "wins": 1, "losses": 2, "totalMatches": 3 })
+    );
+    assert_eq!(response["ad"], serde_json::json!({ "wins": 0, "losses": 0, "totalMatches": 0 }));
+    assert_eq!(
+        response["rivals"],
+        serde_json::json!([
+            { "opponent": "bob", "wins": 2, "losses": 1, "totalMatches": 3 },
+            { "opponent": "carol", "wins": 0, "losses": 1, "totalMatches": 1 },
+        ])
+    );
+    assert_eq!(response["top"], serde_json::json!([{ "opponent": "bob" }]));
+}
+
+/// Thắng / thua theo map và hero của từng người chơi, cùng tỉ lệ chọn / thắng toàn cục.
+#[tokio::test(flavor = "multi_thread")]
+async fn map_and_hero_stats_are_tracked_per_player_and_globally() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    let played = |match_id: &str, winner: &str, loser: &str, map_name: &str, heroes: (Option<&str>, Option<&str>)| {
+        Operation::RecordMatch(MatchOutcome {
+            map_name: map_name.to_string(),
+            winner_hero: heroes.0.map(str::to_string),
+            loser_hero: heroes.1.map(str::to_string),
+            ..outcome(match_id, winner, loser)
+        })
+    };
+    let matches = vec![
+        played("m1", "alice", "bob", "Arena", (Some("Ryu"), Some("Ken"))),
+        played("m2", "bob", "alice", "Dojo", (Some("Ken"), Some("Chun"))),
+        played("m3", "alice", "carol", "Arena", (Some("Ryu"), None)),
+        played("m4", "dave", "carol", "Dojo", (None, None)),
+    ];
+    submit(&chain, application_id, matches).await;
+
+    let query = "query { \
+        playerMapStats(userId: \"alice\") { key matches wins losses winRate } \
+        playerHeroStats(userId: \"alice\") { key matches wins losses winRate } \
+        mapStats { key picks pickRate winRate } \
+        heroStats { key picks wins pickRate winRate } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(
+        response["playerMapStats"],
+        serde_json::json!([
+            { "key": "Arena", "matches": 2, "wins": 2, "losses": 0, "winRate": 1.0 },
+            { "key": "Dojo", "matches": 1, "wins": 0, "losses": 1, "winRate": 0.0 },
+        ])
+    );
+    assert_eq!(
+        response["playerHeroStats"],
+        serde_json::json!([
+            { "key": "Chun", "matches": 1, "wins": 0, "losses": 1, "winRate": 0.0 },
+            { "key": "Ryu", "matches": 2, "wins": 2, "losses": 0, "winRate": 1.0 },
+        ])
+    );
+    assert_eq!(
+        response["mapStats"],
+        serde_json::json!([
+            { "key": "Arena", "picks": 2, "pickRate": 0.5, "winRate": null },
+            { "key": "Dojo", "picks": 2, "pickRate": 0.5, "winRate": null },
+        ])
+    );
+    assert_eq!(
+        response["heroStats"],
+        serde_json::json!([
+            { "key": "Ken", "picks": 2, "wins": 1, "pickRate": 0.5, "winRate": 0.5 },
+            { "key": "Ryu", "picks": 2, "wins": 2, "pickRate": 0.5, "winRate": 1.0 },
+            { "key": "Chun", "picks": 1, "wins": 0, "pickRate": 0.25, "winRate": 0.0 },
+        ])
+    );
+}
+
+/// Quá `SCORE_HISTORY_LEN` điểm thì nửa cũ của `scoreHistory` được thưa bớt (giữ một điểm mỗi hai điểm),
+/// nửa mới giữ nguyên; `from` / `to` lọc theo thời gian trận.
+#[tokio::test(flavor = "multi_thread")]
+async fn score_history_is_downsampled_past_the_cap() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    // 201 trận alice thắng, 50 trận mỗi block (block cuối một trận), mỗi block một thời điểm khác nhau.
+    let match_ids = (0..=SCORE_HISTORY_LEN).map(|i| format!("m{i}")).collect::<Vec<_>>();
+    for chunk in match_ids.chunks(50) {
+        validator.clock().add(TimeDelta::from_secs(60));
+        let operations = chunk.iter().flat_map(|match_id| record(match_id, "alice", "bob")).collect();
+        submit(&chain, application_id, operations).await;
+    }
+
+    let query = "query { scoreHistory(userId: \"alice\") { timestamp score matchId } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    let history = response["scoreHistory"].as_array().expect("Failed to get the score history");
+    let match_id = |point: &serde_json::Value| point["matchId"].as_str().unwrap().to_string();
+    // 201 điểm: 100 điểm cũ nhất còn 50 (m1, m3, ..., m99), 101 điểm mới hơn giữ nguyên (m100..m200).
+    assert_eq!(history.len(), 151);
+    assert_eq!(match_id(&history[0]), "m1");
+    assert_eq!(match_id(&history[1]), "m3");
+    assert_eq!(match_id(&history[49]), "m99");
+    assert_eq!(match_id(&history[50]), "m100");
+    assert_eq!(match_id(&history[150]), "m200");
+    assert_eq!(history[150]["score"].as_u64(), Some(201));
+    assert!(history.windows(2).all(|pair| pair[0]["score"].as_u64() < pair[1]["score"].as_u64()));
+
+    // Block thứ tư: m150..m199.
+    let from = history[100]["timestamp"].as_u64().expect("Failed to get a timestamp");
+    let to = history[149]["timestamp"].as_u64().expect("Failed to get a timestamp");
+    assert_eq!(match_id(&history[100]), "m150");
+    let query = format!("query {{ scoreHistory(userId: \"alice\", from: {from}, to: {to}) {{ matchId }} }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    let ranged = response["scoreHistory"].as_array().expect("Failed to get the score history");
+    assert_eq!(ranged.len(), 50);
+    assert_eq!(match_id(&ranged[0]), "m150");
+    assert_eq!(match_id(&ranged[49]), "m199");
+}
+
+/// `GetSnapshot` / `GetPlayerStats` trả dữ liệu qua `LeaderboardResponse` (để app khác đọc qua `call_application`),
+/// các operation còn lại trả về `Ok`.
+#[tokio::test(flavor = "multi_thread")]
+async fn read_operations_return_their_data_in_the_response() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<LeaderboardAbi, Parameters, Option<LeaderboardConfig>>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = create_leaderboard(&mut chain, module_id, None).await;
+
+    let mut operations = record("m1", "alice", "bob").to_vec();
+    operations.push(Operation::TakeSnapshot { id: "s1".to_string(), n: 8 });
+    let written = responses(&chain, application_id, operations).await;
+    assert_matches!(written[..], [LeaderboardResponse::Ok, LeaderboardResponse::Ok, LeaderboardResponse::Ok]);
 
-    let final_value = initial_state + increment;
-    let QueryOutcome { response, .. } =
-        chain.graphql_query(application_id, "query { value }").await;
-    let state_value = response["value"].as_u64().expect("Failed to get the u64");
-    assert_eq!(state_value, final_value);
+    let operations = vec![
+        Operation::GetSnapshot { id: "s1".to_string() },
+        Operation::GetSnapshot { id: "missing".to_string() },
+        Operation::GetPlayerStats { user_id: "bob".to_string() },
+        Operation::GetPlayerStats { user_id: "ghost".to_string() },
+    ];
+    let read = responses(&chain, application_id, operations).await;
+    assert_eq!(read.len(), 4);
+    assert_matches!(&read[0], LeaderboardResponse::Snapshot(Some(snapshot)) => {
+        let players = snapshot.entries.iter().map(|ranked| ranked.entry.user_id.as_str()).collect::<Vec<_>>();
+        assert_eq!(players, ["alice", "bob"]);
+    });
+    assert_matches!(read[1], LeaderboardResponse::Snapshot(None));
+    assert_matches!(&read[2], LeaderboardResponse::Stats(Some(standing)) => {
+        assert_eq!(standing.entry.total_losses, 1);
+        assert_eq!(standing.rank, Some(2));
+        assert_eq!(standing.total_players, 2);
+    });
+    assert_matches!(read[3], LeaderboardResponse::Stats(None));
 }
-// ANCHOR_END: counter_integration_test
@@ -13,13 +13,13 @@ use linera_sdk::{
     views::{RootView, View},
     Contract, ContractRuntime,
 };
-use tournament::{TournamentAbi, Operation};
+use leaderboard::{LeaderboardResponse, Operation as LeaderboardOperation};
+use tournament::{Operation, Parameters, TournamentAbi};
 
 linera_sdk::contract!(TournamentContract);
 
 pub struct TournamentContract {
     state: TournamentState,
-    #[allow(dead_code)]
     runtime: ContractRuntime<Self>,
 }
 
@@ -28,7 +28,7 @@ impl WithContractAbi for TournamentContract {
 }
 
 impl Contract for TournamentContract {
-    type Parameters = ();
+    type Parameters = Parameters;
     type InstantiationArgument = Option<Vec<String>>;// top8 players passed when creating the app
     type Message = (); // chưa dùng cross-chain
     type EventValue = ();
@@ -108,6 +108,9 @@ impl Contract for TournamentContract {
 				info!("Đóng giải đấu, trạng thái: Finished");
 				self.state.status.set("Finished".to_string());
 			}
+            Operation::SeedFromSnapshot { snapshot_id } => {
+                self.seed_from_snapshot(snapshot_id).await;
+            }
         }
 
         // save() trong contract là async -> dùng .await
@@ -123,3 +126,57 @@ impl Contract for TournamentContract {
         self.state.save().await.expect("Không thể lưu trạng thái");
     }
 }
+
+impl TournamentContract {
+    /// Signer của block là admin: `Parameters::admins`, hoặc các owner của chain nếu danh sách rỗng.
+    fn is_admin(&mut self) -> bool {
+        let params: Parameters = self.runtime.application_parameters();
+        match self.runtime.authenticated_signer() {
+            Some(signer) if params.admins.is_empty() => {
+                self.runtime.chain_ownership().all_owners().any(|owner| *owner == signer)
+            }
+            Some(signer) => params.admins.contains(&signer),
+            None => false,
+        }
+    }
+
+    /// Đọc snapshot từ leaderboard và dùng nó làm danh sách người chơi + thứ tự hạt giống.
+    /// Chỉ admin, chỉ seed một lần và chỉ trước khi có kết quả trận nào.
+    /// Người chơi đã `Register` trước đó bị thay bằng danh sách trong snapshot.
+    async fn seed_from_snapshot(&mut self, snapshot_id: String) {
+        if !self.is_admin() {
+            info!("SeedFromSnapshot bị từ chối: signer {:?} không phải admin", self.runtime.authenticated_signer());
+            return;
+        }
+        if !self.state.seed_snapshot_id.get().is_empty() {
+            info!("Giải đấu đã seed từ snapshot {}, bỏ qua", self.state.seed_snapshot_id.get());
+            return;
+        }
+        let has_results = self.state.results.count().await.expect("Lỗi đếm kết quả") > 0;
+        if has_results || self.state.tournament_leaderboard.count().await.expect("Lỗi đếm leaderboard") > 0 {
+            info!("Giải đấu đã có kết quả trận, không thể seed từ snapshot {}", snapshot_id);
+            return;
+        }
+        let params: Parameters = self.runtime.application_parameters();
+        let Some(leaderboard_id) = params.leaderboard_id else {
+            info!("Thiếu leaderboard_id trong Parameters, không thể seed");
+            return;
+        };
+
+        let op = LeaderboardOperation::GetSnapshot { id: snapshot_id.clone() };
+        let response = self.runtime.call_application(true, leaderboard_id, &op);
+        let LeaderboardResponse::Snapshot(Some(snapshot)) = response else {
+            info!("Không tìm thấy snapshot {} trên leaderboard", snapshot_id);
+            return;
+        };
+
+        let seeds: Vec<String> = snapshot.entries.into_iter().map(|ranked| ranked.entry.user_id).collect();
+        info!("Seed giải đấu từ snapshot {}: {:?}", snapshot_id, seeds);
+        self.state.participants.clear();
+        for player in &seeds {
+            self.state.participants.insert(player, true).expect("Lỗi lưu participant");
+        }
+        self.state.seeds.set(seeds);
+        self.state.seed_snapshot_id.set(snapshot_id);
+    }
+}
@@ -6,7 +6,8 @@
 
 use serde::{Deserialize, Serialize};
 use async_graphql::{Request, Response};
-use linera_sdk::linera_base_types::{ContractAbi, ServiceAbi};
+use leaderboard::LeaderboardAbi;
+use linera_sdk::linera_base_types::{AccountOwner, ApplicationId, ContractAbi, ServiceAbi};
 
 /// Operation của Tournament dùng enum để chứa nhiều loại thao tác.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -15,6 +16,19 @@ pub enum Operation {
     Register { player: String },
     RecordMatch { match_id: String, winner: String, loser: String },
     CloseTournament,
+    /// Lấy người chơi và thứ tự hạt giống từ snapshot bất biến của leaderboard (qua `call_application`).
+    /// Chỉ admin (`Parameters::admins`), chỉ một lần và trước khi có kết quả trận nào;
+    /// danh sách người chơi được thay bằng snapshot (người đã `Register` trước đó bị bỏ).
+    SeedFromSnapshot { snapshot_id: String },
+}
+
+/// Tham số Tournament (`--json-parameters`).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Parameters {
+    pub leaderboard_id: Option<ApplicationId<LeaderboardAbi>>,
+    /// Signer được phép seed giải đấu; rỗng = các owner của chain.
+    pub admins: Vec<AccountOwner>,
 }
 
 /// Message (cross-chain) triển khai dưới dạng struct để nhất quán.
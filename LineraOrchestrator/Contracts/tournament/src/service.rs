@@ -85,12 +85,27 @@ impl MutationRoot {
         self.runtime.schedule_operation(&op);
         true
     }
+
+    async fn seed_from_snapshot(&self, snapshot_id: String) -> bool {
+        let op = Operation::SeedFromSnapshot { snapshot_id };
+        self.runtime.schedule_operation(&op);
+        true
+    }
 }
 
 struct QueryRoot {
     state: Arc<TournamentState>,
 }
 
+/// Một cặp đấu vòng đầu; `seed_b` / `player_b` là None khi `player_a` được miễn đấu (bye).
+#[derive(SimpleObject, Clone)]
+struct BracketPairing {
+    seed_a: u64,
+    player_a: String,
+    seed_b: Option<u64>,
+    player_b: Option<String>,
+}
+
 #[derive(SimpleObject, Clone)]
 struct LeaderboardEntry {
     player: String,
@@ -162,6 +177,45 @@ impl QueryRoot {
         results.sort_by(|a, b| b.score.cmp(&a.score));
         results
     }
+
+    /// Hạt giống theo thứ hạng trong snapshot leaderboard.
+    async fn seeds(&self) -> Vec<String> {
+        self.state.seeds.get().clone()
+    }
+
+    async fn seed_snapshot_id(&self) -> Option<String> {
+        let id = self.state.seed_snapshot_id.get();
+        if id.is_empty() {
+            None
+        } else {
+            Some(id.clone())
+        }
+    }
+
+    /// Cặp đấu vòng đầu xác định từ hạt giống: 1 vs N, 2 vs N-1, ...
+    /// Số hạt giống lẻ thì hạt giống ở giữa được miễn đấu (bye), nằm cuối danh sách.
+    async fn bracket(&self) -> Vec<BracketPairing> {
+        let seeds = self.state.seeds.get();
+        let n = seeds.len();
+        let mut pairings: Vec<BracketPairing> = (0..n / 2)
+            .map(|i| BracketPairing {
+                seed_a: i as u64 + 1,
+                player_a: seeds[i].clone(),
+                seed_b: Some((n - i) as u64),
+                player_b: Some(seeds[n - 1 - i].clone()),
+            })
+            .collect();
+        if n % 2 == 1 {
+            pairings.push(BracketPairing {
+                seed_a: (n / 2) as u64 + 1,
+                player_a: seeds[n / 2].clone(),
+                seed_b: None,
+                player_b: None,
+            });
+        }
+        pairings
+    }
+
 	async fn onchain_op_id(&self) -> Option<String> {
         let id = self.state.opid.get();
         if id.is_empty() {
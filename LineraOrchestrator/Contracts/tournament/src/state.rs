@@ -19,4 +19,6 @@ pub struct TournamentState {
     pub runner_up: RegisterView<String>, // top 2
     pub tournament_leaderboard: MapView<String, u64>,
 	pub opid: RegisterView<String>,
+    pub seed_snapshot_id: RegisterView<String>, // snapshot leaderboard dùng để xếp hạt giống ("" = chưa seed)
+    pub seeds: RegisterView<Vec<String>>, // hạt giống 1..N theo thứ hạng trong snapshot
 }
@@ -0,0 +1,175 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Integration tests for seeding a tournament from a leaderboard snapshot.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use leaderboard::{LeaderboardAbi, LeaderboardConfig};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, ApplicationId, ModuleId},
+    test::{ActiveChain, QueryOutcome, TestValidator},
+};
+use tournament::{Operation, Parameters, TournamentAbi};
+
+/// Leaderboard có snapshot "s1" với thứ tự alice (2 thắng), bob (1 thắng), carol (0 thắng).
+async fn leaderboard_with_snapshot(chain: &mut ActiveChain) -> ApplicationId<LeaderboardAbi> {
+    let module = chain
+        .publish_bytecode_files_in::<LeaderboardAbi, leaderboard::Parameters, Option<LeaderboardConfig>>(
+            "../leaderboard",
+        )
+        .await;
+    let parameters = leaderboard::Parameters {
+        authorized_owners: vec![AccountOwner::from(chain.public_key())],
+        ..leaderboard::Parameters::default()
+    };
+    let leaderboard_id = chain.create_application(module, parameters, None, vec![]).await;
+    for (match_id, winner, loser) in [("m1", "alice", "bob"), ("m2", "alice", "carol"), ("m3", "bob", "carol")] {
+        record(chain, leaderboard_id, match_id, winner, loser).await;
+    }
+    take_snapshot(chain, leaderboard_id, "s1").await;
+    leaderboard_id
+}
+
+async fn record(
+    chain: &ActiveChain,
+    leaderboard_id: ApplicationId<LeaderboardAbi>,
+    match_id: &str,
+    winner: &str,
+    loser: &str,
+) {
+    chain
+        .add_block(|block| {
+            for (user_id, is_winner) in [(winner, true), (loser, false)] {
+                let operation = leaderboard::Operation::RecordScore {
+                    user_id: user_id.to_string(),
+                    is_winner,
+                    match_id: match_id.to_string(),
+                    match_type: "Ranked".to_string(),
+                    map_name: "Arena".to_string(),
+                };
+                block.with_operation(leaderboard_id, operation);
+            }
+        })
+        .await;
+}
+
+async fn take_snapshot(chain: &ActiveChain, leaderboard_id: ApplicationId<LeaderboardAbi>, id: &str) {
+    chain
+        .add_block(|block| {
+            block.with_operation(leaderboard_id, leaderboard::Operation::TakeSnapshot { id: id.to_string(), n: 8 });
+        })
+        .await;
+}
+
+async fn create_tournament(
+    chain: &mut ActiveChain,
+    module_id: ModuleId<TournamentAbi, Parameters, Option<Vec<String>>>,
+    leaderboard_id: ApplicationId<LeaderboardAbi>,
+    admins: Vec<AccountOwner>,
+) -> ApplicationId<TournamentAbi> {
+    let parameters = Parameters { leaderboard_id: Some(leaderboard_id), admins };
+    chain.create_application(module_id, parameters, None, vec![]).await
+}
+
+async fn submit(chain: &ActiveChain, tournament_id: ApplicationId<TournamentAbi>, operation: Operation) {
+    chain
+        .add_block(|block| {
+            block.with_operation(tournament_id, operation);
+        })
+        .await;
+}
+
+fn seed(snapshot_id: &str) -> Operation {
+    Operation::SeedFromSnapshot { snapshot_id: snapshot_id.to_string() }
+}
+
+async fn seeds(chain: &ActiveChain, tournament_id: ApplicationId<TournamentAbi>) -> serde_json::Value {
+    let query = "query { seeds seedSnapshotId bracket { seedA playerA seedB playerB } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(tournament_id, query).await;
+    response
+}
+
+/// Hạt giống theo đúng thứ tự snapshot; số hạt giống lẻ thì hạt giống ở giữa được miễn đấu.
+#[tokio::test(flavor = "multi_thread")]
+async fn seeds_follow_the_snapshot_order() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<TournamentAbi, Parameters, Option<Vec<String>>>().await;
+    let mut chain = validator.new_chain().await;
+    let leaderboard_id = leaderboard_with_snapshot(&mut chain).await;
+    let tournament_id = create_tournament(&mut chain, module_id, leaderboard_id, Vec::new()).await;
+
+    submit(&chain, tournament_id, seed("s1")).await;
+
+    let response = seeds(&chain, tournament_id).await;
+    assert_eq!(response["seeds"], serde_json::json!(["alice", "bob", "carol"]));
+    assert_eq!(response["seedSnapshotId"], "s1");
+    assert_eq!(
+        response["bracket"],
+        serde_json::json!([
+            { "seedA": 1, "playerA": "alice", "seedB": 3, "playerB": "carol" },
+            { "seedA": 2, "playerA": "bob", "seedB": null, "playerB": null },
+        ])
+    );
+}
+
+/// Signer không nằm trong `Parameters::admins` không seed được.
+#[tokio::test(flavor = "multi_thread")]
+async fn only_admins_can_seed() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<TournamentAbi, Parameters, Option<Vec<String>>>().await;
+    let mut chain = validator.new_chain().await;
+    let other = validator.new_chain().await;
+    let leaderboard_id = leaderboard_with_snapshot(&mut chain).await;
+    let admins = vec![AccountOwner::from(other.public_key())];
+    let tournament_id = create_tournament(&mut chain, module_id, leaderboard_id, admins).await;
+
+    submit(&chain, tournament_id, seed("s1")).await;
+
+    let response = seeds(&chain, tournament_id).await;
+    assert_eq!(response["seeds"], serde_json::json!([]));
+    assert!(response["seedSnapshotId"].is_null());
+}
+
+/// Chỉ seed một lần: snapshot sau (thứ tự khác) không thay hạt giống đã có.
+#[tokio::test(flavor = "multi_thread")]
+async fn seeding_happens_only_once() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<TournamentAbi, Parameters, Option<Vec<String>>>().await;
+    let mut chain = validator.new_chain().await;
+    let leaderboard_id = leaderboard_with_snapshot(&mut chain).await;
+    let tournament_id = create_tournament(&mut chain, module_id, leaderboard_id, Vec::new()).await;
+
+    submit(&chain, tournament_id, seed("s1")).await;
+    for match_id in ["m4", "m5", "m6"] {
+        record(&chain, leaderboard_id, match_id, "carol", "alice").await;
+    }
+    take_snapshot(&chain, leaderboard_id, "s2").await;
+    submit(&chain, tournament_id, seed("s2")).await;
+
+    let response = seeds(&chain, tournament_id).await;
+    assert_eq!(response["seeds"], serde_json::json!(["alice", "bob", "carol"]));
+    assert_eq!(response["seedSnapshotId"], "s1");
+}
+
+/// Đã có kết quả trận thì không seed được nữa.
+#[tokio::test(flavor = "multi_thread")]
+async fn seeding_is_rejected_after_results() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<TournamentAbi, Parameters, Option<Vec<String>>>().await;
+    let mut chain = validator.new_chain().await;
+    let leaderboard_id = leaderboard_with_snapshot(&mut chain).await;
+    let tournament_id = create_tournament(&mut chain, module_id, leaderboard_id, Vec::new()).await;
+
+    let result = Operation::RecordMatch {
+        match_id: "Q1".to_string(),
+        winner: "alice".to_string(),
+        loser: "bob".to_string(),
+    };
+    submit(&chain, tournament_id, result).await;
+    submit(&chain, tournament_id, seed("s1")).await;
+
+    let response = seeds(&chain, tournament_id).await;
+    assert_eq!(response["seeds"], serde_json::json!([]));
+    assert!(response["seedSnapshotId"].is_null());
+}
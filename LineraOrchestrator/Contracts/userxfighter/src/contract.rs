@@ -12,7 +12,8 @@ use self::state::{UserXfighterState, Transaction};
 use log::{info, error};
 
 // Sử dụng full path cho các type
-use userxfighter::{Operation, BettingMessage, Parameters, UserXfighterAbi};
+use userxfighter::{Operation, BettingMessage, Parameters, StatsMessage, UserXfighterAbi};
+use leaderboard::{LeaderboardResponse, Operation as LeaderboardOperation};
 
 linera_sdk::contract!(UserXfighterContract);
 
@@ -98,6 +99,28 @@ impl Contract for UserXfighterContract {
             Operation::Transfer { to, amount } => {
                 info!("Transfer {} to {}", amount, to);
             }
+
+            Operation::RequestStats { user_id } => {
+                let is_owner = match self.runtime.authenticated_signer() {
+                    Some(signer) => self.runtime.chain_ownership().all_owners().any(|owner| *owner == signer),
+                    None => false,
+                };
+                if !is_owner {
+                    error!("[UserXFighter] RequestStats rejected: signer is not an owner of this chain");
+                    return;
+                }
+                let params: Parameters = self.runtime.application_parameters();
+                match params.leaderboard_chain_id {
+                    Some(leaderboard_chain_id) => {
+                        self.runtime
+                            .prepare_message(BettingMessage::Stats(StatsMessage::QueryStats { user_id: user_id.clone() }))
+                            .with_tracking()
+                            .send_to(leaderboard_chain_id);
+                        info!("[UserXFighter] Requested stats for {} from chain {}", user_id, leaderboard_chain_id);
+                    }
+                    None => error!("[UserXFighter] No leaderboard_chain_id in parameters, cannot request stats"),
+                }
+            }
         }
         
         // Luôn save state sau operation
@@ -219,6 +242,50 @@ impl Contract for UserXfighterContract {
                 
                 info!("[UserXFighter] Refunded {} for bet {}", amount, bet_id);
             }
+
+            BettingMessage::Stats(StatsMessage::QueryStats { user_id }) => {
+                // Chạy trên publisher chain: đọc từ leaderboard rồi trả lời chain đã hỏi.
+                if self.runtime.message_is_bouncing().unwrap_or(false) {
+                    info!("[UserXFighter] QueryStats for {} bounced", user_id);
+                    return;
+                }
+                let Some(origin) = self.runtime.message_origin_chain_id() else {
+                    error!("[UserXFighter] QueryStats without origin chain");
+                    return;
+                };
+                let params: Parameters = self.runtime.application_parameters();
+                let Some(leaderboard_id) = params.leaderboard_id else {
+                    error!("[UserXFighter] No leaderboard_id in parameters, cannot answer QueryStats");
+                    return;
+                };
+                let op = LeaderboardOperation::GetPlayerStats { user_id: user_id.clone() };
+                let standing = match self.runtime.call_application(true, leaderboard_id, &op) {
+                    LeaderboardResponse::Stats(standing) => standing,
+                    other => {
+                        error!("[UserXFighter] Unexpected leaderboard response: {:?}", other);
+                        None
+                    }
+                };
+                self.runtime
+                    .prepare_message(BettingMessage::Stats(StatsMessage::StatsReply { user_id, standing }))
+                    .with_tracking()
+                    .send_to(origin);
+            }
+
+            BettingMessage::Stats(StatsMessage::StatsReply { user_id, standing }) => {
+                match standing {
+                    Some(standing) => {
+                        info!("[UserXFighter] Stats for {}: rank {:?}", user_id, standing.rank);
+                        self.state.player_stats.insert(&user_id, standing)
+                            .expect("Failed to store player stats");
+                    }
+                    None => {
+                        info!("[UserXFighter] Leaderboard has no stats for {}", user_id);
+                        self.state.player_stats.remove(&user_id)
+                            .expect("Failed to remove player stats");
+                    }
+                }
+            }
         }
         
         if let Err(e) = self.state.save().await {
@@ -5,7 +5,8 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 use async_graphql::{Request, Response};
-use linera_sdk::linera_base_types::{ContractAbi, ServiceAbi, ChainId, ModuleId};
+use leaderboard::{LeaderboardAbi, PlayerStanding};
+use linera_sdk::linera_base_types::{ApplicationId, ContractAbi, ServiceAbi, ChainId, ModuleId};
 use serde::{Deserialize, Serialize};
 
 
@@ -14,6 +15,9 @@ pub enum Operation {
     Deposit { amount: u64 },
     Withdraw { amount: u64 },
     Transfer { to: String, amount: u64 },
+    /// Hỏi leaderboard (trên publisher chain) thống kê của `user_id`; kết quả về qua `StatsReply`.
+    /// Chỉ owner của chain được gửi.
+    RequestStats { user_id: String },
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -21,9 +25,15 @@ pub struct Parameters {
     pub tournament_id: String,
     pub user_xfighter_module: ModuleId,
 	pub tournament_chain_id: ChainId,
+    /// Leaderboard để tra cứu thống kê, và chain nơi nó được tạo (publisher chain).
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId<LeaderboardAbi>>,
+    #[serde(default)]
+    pub leaderboard_chain_id: Option<ChainId>,
 }
 
-/// Message mà UserXfighter nhận từ Tournament (phải giống hệt với BettingMessage trong tournament)
+/// Message mà UserXfighter nhận: các message cược (debit / credit / refund) và message thống kê.
+/// Thứ tự variant là một phần của định dạng BCS: chỉ thêm variant mới vào cuối.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum BettingMessage {
     DebitForBet {
@@ -44,9 +54,24 @@ pub enum BettingMessage {
         bet_id: String,
         match_id: String,
     },
+    /// Tra cứu thống kê, chỉ đi giữa các chain của UserXfighter (không liên quan tới cược).
+    Stats(StatsMessage),
+}
+
+/// Message tra cứu thống kê giữa user chain và publisher chain (nơi có leaderboard).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum StatsMessage {
+    /// User chain -> publisher chain: hỏi thống kê của `user_id`.
+    QueryStats {
+        user_id: String,
+    },
+    /// Publisher chain -> user chain: thống kê đọc từ leaderboard (None nếu người chơi chưa có dữ liệu).
+    StatsReply {
+        user_id: String,
+        standing: Option<PlayerStanding>,
+    },
 }
 
-// UserXfighter nhận BettingMessage từ tournament
 pub type Message = BettingMessage;
 
 pub struct UserXfighterAbi;
@@ -13,6 +13,7 @@ use linera_sdk::{abi::WithServiceAbi, views::View, Service, ServiceRuntime};
 
 // Sửa import: dùng userxfighter::
 use userxfighter::{Operation, UserXfighterAbi};
+use leaderboard::PlayerStanding;
 
 linera_sdk::service!(UserXfighterService);
 
@@ -106,6 +107,11 @@ impl QueryRoot {
         self.state.tournament_app_id.get().clone()
     }
 		
+    /// Thống kê / thứ hạng gần nhất nhận từ leaderboard (gửi `requestStats` để làm mới).
+    async fn player_stats(&self, user_id: String) -> Option<PlayerStanding> {
+        self.state.player_stats.get(&user_id).await.ok().flatten()
+    }
+
 	 async fn get_user_chain(&self, user_id: String) -> Option<String> {
         self.state.user_chains.get(&user_id).await
             .ok()
@@ -131,4 +137,11 @@ impl MutationRoot {
         self.runtime.schedule_operation(&op);
         true
     }
+
+    /// Hỏi leaderboard thống kê của `user_id`; kết quả được lưu khi `StatsReply` về tới chain này.
+    async fn request_stats(&self, user_id: String) -> bool {
+        let op = Operation::RequestStats { user_id };
+        self.runtime.schedule_operation(&op);
+        true
+    }
 }
\ No newline at end of file
@@ -5,6 +5,7 @@
 use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext};
 use serde::{Deserialize, Serialize};
 use linera_sdk::linera_base_types::ChainId;
+use leaderboard::PlayerStanding;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Transaction {
@@ -25,4 +26,5 @@ pub struct UserXfighterState {
     pub processed_bets: MapView<String, bool>, // bet_id -> processed
 	pub user_chains: MapView<String, ChainId>, // THÊM: user_id -> chain_id  tournament_id -> tournament_chain_id
     pub tournament_chain_id: RegisterView<Option<ChainId>>, // THÊM: chain ID của tournament
+    pub player_stats: MapView<String, PlayerStanding>, // user_id -> thống kê nhận qua StatsReply
 }
\ No newline at end of file
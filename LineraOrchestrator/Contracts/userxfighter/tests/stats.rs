@@ -0,0 +1,70 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Integration tests for stats lookups between user chains and the publisher chain.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use leaderboard::{LeaderboardAbi, LeaderboardConfig};
+use linera_sdk::{
+    linera_base_types::AccountOwner,
+    test::{QueryOutcome, TestValidator},
+};
+use userxfighter::{Operation, Parameters, UserXfighterAbi};
+
+/// `RequestStats` trên user chain -> `QueryStats` tới publisher chain -> `StatsReply` lưu lại trên user chain.
+#[tokio::test(flavor = "multi_thread")]
+async fn requested_stats_come_back_to_the_user_chain() {
+    let (validator, module_id) = TestValidator::with_current_module::<UserXfighterAbi, Parameters, ()>().await;
+    let mut publisher = validator.new_chain().await;
+    let user_chain = validator.new_chain().await;
+
+    let leaderboard_module = publisher
+        .publish_bytecode_files_in::<LeaderboardAbi, leaderboard::Parameters, Option<LeaderboardConfig>>(
+            "../leaderboard",
+        )
+        .await;
+    let leaderboard_parameters = leaderboard::Parameters {
+        authorized_owners: vec![AccountOwner::from(publisher.public_key())],
+        ..leaderboard::Parameters::default()
+    };
+    let leaderboard_id = publisher.create_application(leaderboard_module, leaderboard_parameters, None, vec![]).await;
+    publisher
+        .add_block(|block| {
+            for (user_id, is_winner) in [("alice", true), ("bob", false)] {
+                let operation = leaderboard::Operation::RecordScore {
+                    user_id: user_id.to_string(),
+                    is_winner,
+                    match_id: "m1".to_string(),
+                    match_type: "Ranked".to_string(),
+                    map_name: "Arena".to_string(),
+                };
+                block.with_operation(leaderboard_id, operation);
+            }
+        })
+        .await;
+
+    let parameters = Parameters {
+        tournament_id: String::new(),
+        user_xfighter_module: module_id.forget_abi(),
+        tournament_chain_id: publisher.id(),
+        leaderboard_id: Some(leaderboard_id),
+        leaderboard_chain_id: Some(publisher.id()),
+    };
+    let application_id = publisher.create_application(module_id, parameters, (), vec![]).await;
+
+    user_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RequestStats { user_id: "alice".to_string() });
+        })
+        .await;
+    publisher.handle_received_messages().await;
+    user_chain.handle_received_messages().await;
+
+    let query = "query { alice: playerStats(userId: \"alice\") { entry { totalWins totalLosses } } \
+        bob: playerStats(userId: \"bob\") { entry { totalWins } } }";
+    let QueryOutcome { response, .. } = user_chain.graphql_query(application_id, query).await;
+    assert_eq!(response["alice"]["entry"]["totalWins"].as_u64(), Some(1));
+    assert_eq!(response["alice"]["entry"]["totalLosses"].as_u64(), Some(0));
+    assert!(response["bob"].is_null());
+}
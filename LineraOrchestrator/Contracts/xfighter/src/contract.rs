@@ -8,13 +8,13 @@ mod state;
 use log::{debug, error, info};
 
 use self::state::{MatchResult, XfighterState};
-use linera_sdk::linera_base_types::{ApplicationPermissions, Amount, ChainId};
+use linera_sdk::linera_base_types::{ApplicationPermissions, Amount, ChainId, Ed25519PublicKey};
 use linera_sdk::{abi::WithContractAbi, views::{RootView, View}, Contract, ContractRuntime};
 
-use xfighter::{Operation, XfighterAbi, FactoryOperation};
+use xfighter::{FactoryOperation, MatchResultInput, Operation, RecordScoreResponse, XfighterAbi, XfighterResponse};
 use leaderboard::Operation as LeaderboardOperation;
 use leaderboard::LeaderboardAbi;
-use leaderboard::RecordScoreMessage;
+use leaderboard::{MatchOutcome, Message};
 
 use xfighter::Parameters;
 
@@ -26,7 +26,7 @@ pub struct XfighterContract {
     // Optional: store OpenAndCreate info to log once at finalization
     pending_open_and_create: Option<(String, String, String, String)>,
     // Volatile per-transaction outbound messages to send once on store()
-    pending_outbound: Vec<(ChainId, RecordScoreMessage)>,
+    pending_outbound: Vec<(ChainId, Message)>,
 }
 
 impl WithContractAbi for XfighterContract {
@@ -34,7 +34,7 @@ impl WithContractAbi for XfighterContract {
 }
 
 impl Contract for XfighterContract {
-    type Message = RecordScoreMessage;
+    type Message = Message;
     type InstantiationArgument = ();
     type Parameters = Parameters;
     type EventValue = ChainId;
@@ -76,7 +76,7 @@ impl Contract for XfighterContract {
     }
 
     /// Xử lý Operation (Service / Orchestrator)
-    async fn execute_operation(&mut self, operation: Self::Operation) {
+    async fn execute_operation(&mut self, operation: Self::Operation) -> XfighterResponse {
         match operation {
             // ================= Factory Open Create flow =================
             Operation::Factory(factory_op) => match factory_op {
@@ -101,6 +101,9 @@ impl Contract for XfighterContract {
                     let child_params = Parameters {
                         xfighter_module: module_id.clone(),
                         leaderboard_id,
+                        board_id: params.board_id.clone(),
+                        game_server_keys: self.game_server_keys(),
+                        admins: params.admins.clone(),
                     };
 
                     // 4. Auto-instantiate app con trên chain mới
@@ -111,7 +114,11 @@ impl Contract for XfighterContract {
                         vec![],
                     );
 
-                    // 5. lưu lại mapping chain/app
+                    // 5. app con gọi leaderboard bằng id của chính nó: factory (writer app) cấp quyền ghi điểm cho nó
+                    let authorize = LeaderboardOperation::AuthorizeApplication { application_id: new_app_id.forget_abi() };
+                    let _ = self.runtime.call_application::<LeaderboardAbi>(true, leaderboard_id, &authorize);
+
+                    // 6. lưu lại mapping chain/app
                     if let Err(e) = self.state.opened_chains.insert(&new_chain_id) {
                         error!("Failed to insert new_chain_id: {:?}", e);
                     }
@@ -126,136 +133,208 @@ impl Contract for XfighterContract {
                         format!("{:?}", module_id),
                         format!("{:?}", params),
                     ));
+                    XfighterResponse::Ok
                 }
             },
 
             // ================= RecordScore flow =================
-            Operation::RecordScore(input) => {
-                // Normalize match_id and chain key
-                let match_id = input.match_id.clone();
-                let chain_id_str = self.runtime.chain_id().to_string();
-
-                // If already recorded for this chain, skip
-                if self
-                    .state
-                    .match_results
-                    .contains_key(&chain_id_str)
-                    .await
-                    .expect("Failed to check if match result exists")
-                {
-                    info!("[XFighter] Match already recorded for chain={}, skipping.", chain_id_str);
-                    return;
-                }
+            Operation::RecordScore(input) => XfighterResponse::RecordScore(self.record_score(input).await),
 
-                // Validate winner_username is one of players
-                if input.winner_username != input.player1_username && input.winner_username != input.player2_username {
-                    info!("[XFighter] Invalid winner username for match_id={}, skipping.", match_id);
-                    return;
+            Operation::RotateGameServerKeys { add, remove } => {
+                if !self.is_admin() {
+                    info!("[XFighter] RotateGameServerKeys rejected: signer={:?}", self.runtime.authenticated_signer());
+                    return XfighterResponse::Unauthorized;
                 }
-
-                // 1) Persist match result in state (under chain key)
-                let match_result_data = MatchResult {
-                    match_id: match_id.clone(),
-                    player1_username: input.player1_username.clone(),
-                    player2_username: input.player2_username.clone(),
-                    winner_username: input.winner_username.clone(),
-                    loser_username: input.loser_username.clone(),
-                    duration_seconds: input.duration_seconds,
-                    timestamp: input.timestamp,
-                    player1_score: input.player1_score,
-                    player2_score: input.player2_score,
-                    map_name: input.map_name.clone(),
-                    match_type: input.match_type.clone(),
-					afk: input.afk.clone(),
-                };
-
-                self.state
-                    .match_results
-                    .insert(&chain_id_str, match_result_data)
-                    .expect("Failed to insert match result");
-
-                // 2) Prepare idempotency composite key "<chainId>:<matchId>"
-                let key = chain_id_str.clone();
-
-                // if already marked as sent, skip enqueue
-                if self.state.sent_messages.get(&key).await.ok().flatten().unwrap_or(false) {
-                    debug!("[XFighter] Outbound messages already marked sent for key={}, skipping.", key);
-                    return;
+                let mut keys = self.game_server_keys();
+                keys.retain(|key| !remove.contains(key));
+                for key in add {
+                    if !keys.contains(&key) {
+                        keys.push(key);
+                    }
                 }
-
-                // Mark sent in state (persist flag) to prevent duplicate sends across re-exec / retries
-                self.state
-                    .sent_messages
-                    .insert(&key, true)
-                    .expect("Failed to insert sent_messages flag");
-
-                // Target info
-                let params: Parameters = self.runtime.application_parameters();
-                let lb_id = params.leaderboard_id;
-                let publisher_chain_id = self.runtime.application_creator_chain_id();
-
-                // ENQUEUE outbound messages (do not send now — we'll send in store())
-                debug!(
-                    "[XFighter] Queued RecordScore messages (deferred) to publisher_chain_id={:?} key={}",
-                    publisher_chain_id, key
-                );
-
-                // Winner
-                self.pending_outbound.push((
-                    publisher_chain_id,
-                    RecordScoreMessage {
-                        user_id: input.winner_username.clone(),
-                        is_winner: true,
-                        match_id: match_id.clone(),
-                    },
-                ));
-
-                // Loser
-                let loser_username = if input.winner_username == input.player1_username {
-                    input.player2_username.clone()
-                } else {
-                    input.player1_username.clone()
-                };
-                self.pending_outbound.push((
-                    publisher_chain_id,
-                    RecordScoreMessage {
-                        user_id: loser_username.clone(),
-                        is_winner: false,
-                        match_id: match_id.clone(),
-                    },
-                ));
-
-                info!(
-                    "[XFighter] Enqueued RecordScore messages for leaderboard_app={:?}, key={}, winner={}, loser={}, match_id={}",
-                    lb_id, key, input.winner_username, loser_username, match_id
-                );
+                if keys.is_empty() {
+                    info!("[XFighter] RotateGameServerKeys rejected: no game server key would remain");
+                    return XfighterResponse::NoGameServerKeys;
+                }
+                info!("[XFighter] Game server keys rotated: {} key(s) active", keys.len());
+                self.state.game_server_keys.set(Some(keys));
+                XfighterResponse::Ok
             }
         }
     }
 
     /// Cross-chain Message
     async fn execute_message(&mut self, message: Self::Message) {
-        // Message delivered to Xfighter instance at publisher chain
-        info!("[XFighter] execute_message received RecordScoreMessage user={} is_winner={} match_id={}",
-            message.user_id, message.is_winner, message.match_id
-        );
-
         // Get leaderboard app id from parameters
         let params: Parameters = self.runtime.application_parameters();
         let lb_id = params.leaderboard_id;
 
-        // Call local (same-chain) leaderboard app
-        let op = LeaderboardOperation::RecordScore {
-            user_id: message.user_id.clone(),
-            is_winner: message.is_winner,
-            match_id: message.match_id.clone(),
+        // Message delivered to Xfighter instance at publisher chain
+        let op = match message {
+            // Legacy per-player message (không có loại trận / map)
+            Message::RecordScore(message) => {
+                info!("[XFighter] execute_message received RecordScoreMessage user={} is_winner={} match_id={}",
+                    message.user_id, message.is_winner, message.match_id
+                );
+                LeaderboardOperation::RecordScore {
+                    user_id: message.user_id,
+                    is_winner: message.is_winner,
+                    match_id: message.match_id,
+                    match_type: String::new(),
+                    map_name: String::new(),
+                }
+            }
+            Message::RecordTypedScore(message) => {
+                info!("[XFighter] execute_message received TypedRecordScoreMessage user={} is_winner={} match_id={} match_type={}",
+                    message.user_id, message.is_winner, message.match_id, message.match_type
+                );
+                LeaderboardOperation::RecordScore {
+                    user_id: message.user_id,
+                    is_winner: message.is_winner,
+                    match_id: message.match_id,
+                    match_type: message.match_type,
+                    map_name: message.map_name,
+                }
+            }
+            Message::MatchOutcome(outcome) => {
+                info!("[XFighter] execute_message received MatchOutcome match_id={} winner={} loser={} match_type={}",
+                    outcome.match_id, outcome.winner, outcome.loser, outcome.match_type
+                );
+                LeaderboardOperation::RecordMatch(outcome)
+            }
         };
 
-        // call_application returns a response;
+        // Call local (same-chain) leaderboard app; call_application returns a response;
         let _ = self.runtime.call_application::<LeaderboardAbi>(true, lb_id, &op);
 
-        info!("[XFighter] Forwarded RecordScore to leaderboard app_id={:?} user={} match_id={}",
-            lb_id, message.user_id, message.match_id
+        info!("[XFighter] Forwarded result to leaderboard app_id={:?}", lb_id);
+    }
+}
+
+impl XfighterContract {
+    /// Khoá game server đang được chấp nhận: bộ khoá đã đổi qua RotateGameServerKeys, hoặc của Parameters.
+    fn game_server_keys(&mut self) -> Vec<Ed25519PublicKey> {
+        match self.state.game_server_keys.get() {
+            Some(keys) => keys.clone(),
+            None => self.runtime.application_parameters().game_server_keys,
+        }
+    }
+
+    /// Signer nằm trong `admins`, hoặc là owner của chain khi `admins` rỗng.
+    fn is_admin(&mut self) -> bool {
+        let params: Parameters = self.runtime.application_parameters();
+        match self.runtime.authenticated_signer() {
+            Some(signer) if params.admins.is_empty() => {
+                self.runtime.chain_ownership().all_owners().any(|owner| *owner == signer)
+            }
+            Some(signer) => params.admins.contains(&signer),
+            None => false,
+        }
+    }
+
+    /// Ghi kết quả một trận và xếp hàng MatchOutcome gửi tới leaderboard; trả về lý do nếu bị từ chối.
+    async fn record_score(&mut self, input: MatchResultInput) -> RecordScoreResponse {
+        let match_id = input.match_id.clone();
+        if let Err(rejection) = input.validate() {
+            info!("[XFighter] Rejected match_id={}: {:?}", match_id, rejection);
+            return rejection;
+        }
+        if let Err(rejection) = input.verify_signature(&self.game_server_keys()) {
+            info!("[XFighter] Rejected match_id={}: {:?}", match_id, rejection);
+            return rejection;
+        }
+
+        // If this match_id is already recorded on this chain, skip
+        if self
+            .state
+            .match_results
+            .contains_key(&match_id)
+            .await
+            .expect("Failed to check if match result exists")
+        {
+            info!("[XFighter] Match already recorded for match_id={}, skipping.", match_id);
+            return RecordScoreResponse::Duplicate;
+        }
+        // Outbound message already marked sent: skip before persisting anything, so a Duplicate leaves no trace
+        if self.state.sent_messages.get(&match_id).await.ok().flatten().unwrap_or(false) {
+            debug!("[XFighter] Outbound messages already marked sent for key={}, skipping.", match_id);
+            return RecordScoreResponse::Duplicate;
+        }
+
+        // 1) Persist match result in state (under match_id)
+        let match_result_data = MatchResult {
+            match_id: match_id.clone(),
+            player1_username: input.player1_username.clone(),
+            player2_username: input.player2_username.clone(),
+            winner_username: input.winner_username.clone(),
+            loser_username: input.loser_username.clone(),
+            duration_seconds: input.duration_seconds,
+            timestamp: input.timestamp,
+            player1_score: input.player1_score,
+            player2_score: input.player2_score,
+            map_name: input.map_name.clone(),
+            match_type: input.match_type.clone(),
+					afk: input.afk.clone(),
+            player1_hero: input.player1_hero.clone(),
+            player2_hero: input.player2_hero.clone(),
+        };
+
+        self.state
+            .match_results
+            .insert(&match_id, match_result_data)
+            .expect("Failed to insert match result");
+        self.state.match_order.push(match_id.clone());
+
+        // 2) Idempotency key for the outbound message (checked above)
+        let key = match_id.clone();
+
+        // Mark sent in state (persist flag) to prevent duplicate sends across re-exec / retries
+        self.state
+            .sent_messages
+            .insert(&key, true)
+            .expect("Failed to insert sent_messages flag");
+
+        // Target info
+        let params: Parameters = self.runtime.application_parameters();
+        let lb_id = params.leaderboard_id;
+        let publisher_chain_id = self.runtime.application_creator_chain_id();
+
+        // ENQUEUE outbound message (do not send now — we'll send in store())
+        debug!(
+            "[XFighter] Queued MatchOutcome message (deferred) to publisher_chain_id={:?} key={}",
+            publisher_chain_id, key
+        );
+
+        // Winner + loser in one message so the leaderboard applies both atomically
+        let player1_won = input.winner_username == input.player1_username;
+        let (loser_username, winner_score, loser_score, winner_hero, loser_hero) = if player1_won {
+            (input.player2_username.clone(), input.player1_score, input.player2_score,
+                input.player1_hero.clone(), input.player2_hero.clone())
+        } else {
+            (input.player1_username.clone(), input.player2_score, input.player1_score,
+                input.player2_hero.clone(), input.player1_hero.clone())
+        };
+        self.pending_outbound.push((
+            publisher_chain_id,
+            Message::MatchOutcome(MatchOutcome {
+                match_id: match_id.clone(),
+                winner: input.winner_username.clone(),
+                loser: loser_username.clone(),
+                match_type: input.match_type.clone(),
+                map_name: input.map_name.clone(),
+                winner_score,
+                loser_score,
+                duration_seconds: input.duration_seconds,
+                winner_hero,
+                loser_hero,
+                board_id: params.board_id.clone(),
+            }),
+        ));
+
+        info!(
+            "[XFighter] Enqueued MatchOutcome message for leaderboard_app={:?}, key={}, winner={}, loser={}, match_id={}",
+            lb_id, key, input.winner_username, loser_username, match_id
         );
+        RecordScoreResponse::Recorded
     }
 }
@@ -4,9 +4,11 @@
 
 /*! ABI of the Xfighter Example Application */
 
-use async_graphql::{InputObject, Request, Response};
+use async_graphql::{Enum, InputObject, Request, Response};
 use leaderboard::LeaderboardAbi;
-use linera_sdk::linera_base_types::{ApplicationId, ContractAbi, ServiceAbi, ModuleId};
+use linera_sdk::linera_base_types::{
+    AccountOwner, ApplicationId, BcsSignable, ContractAbi, Ed25519PublicKey, Ed25519Signature, ServiceAbi, ModuleId,
+};
 use serde::{Deserialize, Serialize};
 
 /// Input cho kết quả trận đấu (client gửi vào GraphQL).
@@ -25,6 +27,130 @@ pub struct MatchResultInput {
     pub map_name: String,
     pub match_type: String,
 	pub afk: Option<String>,
+    pub player1_hero: Option<String>,
+    pub player2_hero: Option<String>,
+    /// Chữ ký Ed25519 (hex) của game server trên `payload()`; bắt buộc khi có `game_server_keys`.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// Dữ liệu được game server ký: toàn bộ `MatchResultInput` trừ chữ ký (kể cả `match_id`, nên không được đổi sau khi ký).
+/// Thông điệp Ed25519 thực sự được ký (`BcsSignable`) là Keccak-256 của `b"MatchResultPayload::"` nối với BCS của struct này:
+/// các trường theo đúng thứ tự dưới đây, u64 little-endian, chuỗi có độ dài ULEB128 đứng trước, Option là 0x00 hoặc 0x01 + giá trị.
+/// Chữ ký 64 byte, gửi dạng hex trong `MatchResultInput::signature`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MatchResultPayload {
+    pub match_id: String,
+    pub player1_username: String,
+    pub player2_username: String,
+    pub winner_username: String,
+    pub loser_username: String,
+    pub duration_seconds: u64,
+    pub timestamp: u64,
+    pub player1_score: u64,
+    pub player2_score: u64,
+    pub map_name: String,
+    pub match_type: String,
+    pub afk: Option<String>,
+    pub player1_hero: Option<String>,
+    pub player2_hero: Option<String>,
+}
+
+impl BcsSignable<'_> for MatchResultPayload {}
+
+impl MatchResultInput {
+    pub fn payload(&self) -> MatchResultPayload {
+        MatchResultPayload {
+            match_id: self.match_id.clone(),
+            player1_username: self.player1_username.clone(),
+            player2_username: self.player2_username.clone(),
+            winner_username: self.winner_username.clone(),
+            loser_username: self.loser_username.clone(),
+            duration_seconds: self.duration_seconds,
+            timestamp: self.timestamp,
+            player1_score: self.player1_score,
+            player2_score: self.player2_score,
+            map_name: self.map_name.clone(),
+            match_type: self.match_type.clone(),
+            afk: self.afk.clone(),
+            player1_hero: self.player1_hero.clone(),
+            player2_hero: self.player2_hero.clone(),
+        }
+    }
+
+    /// Kiểm tra chữ ký bằng một trong các khoá game server đang được chấp nhận.
+    /// Không có khoá nào thì không chữ ký nào hợp lệ: kết quả chưa ký bị coi là thiếu chữ ký, đã ký thì không hợp lệ.
+    pub fn verify_signature(&self, keys: &[Ed25519PublicKey]) -> Result<(), RecordScoreResponse> {
+        let Some(signature) = self.signature.as_ref().filter(|signature| !signature.is_empty()) else {
+            return Err(RecordScoreResponse::MissingSignature);
+        };
+        let signature = hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Ed25519Signature::from_slice(&bytes).ok())
+            .ok_or(RecordScoreResponse::InvalidSignature)?;
+        let payload = self.payload();
+        if keys.iter().any(|key| signature.check(&payload, *key).is_ok()) {
+            Ok(())
+        } else {
+            Err(RecordScoreResponse::InvalidSignature)
+        }
+    }
+
+    /// Kiểm tra dữ liệu trận trước khi ghi (contract và service dùng chung).
+    pub fn validate(&self) -> Result<(), RecordScoreResponse> {
+        if self.match_id.trim().is_empty() {
+            return Err(RecordScoreResponse::InvalidMatchId);
+        }
+        if self.player1_username.is_empty()
+            || self.player2_username.is_empty()
+            || self.player1_username == self.player2_username
+        {
+            return Err(RecordScoreResponse::InvalidPlayers);
+        }
+        if self.winner_username != self.player1_username && self.winner_username != self.player2_username {
+            return Err(RecordScoreResponse::InvalidWinner);
+        }
+        // loser_username có thể bỏ trống; nếu có thì phải là người chơi còn lại.
+        if !self.loser_username.is_empty()
+            && (self.loser_username == self.winner_username
+                || (self.loser_username != self.player1_username && self.loser_username != self.player2_username))
+        {
+            return Err(RecordScoreResponse::InvalidLoser);
+        }
+        Ok(())
+    }
+}
+
+/// Kết quả của RecordScore: đã ghi, hoặc lý do bị bỏ qua.
+#[derive(Enum, Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RecordScoreResponse {
+    /// Đã ghi và xếp hàng gửi MatchOutcome tới leaderboard.
+    Recorded,
+    /// match_id đã được ghi trên chain này.
+    Duplicate,
+    /// match_id rỗng.
+    InvalidMatchId,
+    /// Thiếu người chơi, hoặc hai người chơi trùng tên.
+    InvalidPlayers,
+    /// winner_username không phải một trong hai người chơi.
+    InvalidWinner,
+    /// loser_username không phải người chơi còn lại.
+    InvalidLoser,
+    /// Kết quả không có chữ ký game server.
+    MissingSignature,
+    /// Chữ ký không hợp lệ hoặc không khớp khoá game server nào.
+    InvalidSignature,
+}
+
+/// Phản hồi của operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum XfighterResponse {
+    Ok,
+    /// Operation quản trị bị từ chối (signer không phải admin).
+    Unauthorized,
+    RecordScore(RecordScoreResponse),
+    /// RotateGameServerKeys bị từ chối vì chain sẽ không còn khoá game server nào.
+    NoGameServerKeys,
 }
 
 /// Operation của Xfighter (contract/service cùng dùng).
@@ -32,6 +158,8 @@ pub struct MatchResultInput {
 pub enum Operation {
     RecordScore(MatchResultInput),
     Factory(FactoryOperation),
+    /// Thêm / bỏ khoá game server trên chain này (chỉ admin). Chain con mở sau đó nhận bộ khoá mới.
+    RotateGameServerKeys { add: Vec<Ed25519PublicKey>, remove: Vec<Ed25519PublicKey> },
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -43,16 +171,103 @@ pub enum FactoryOperation {
 pub struct Parameters {
     pub xfighter_module: ModuleId,
 	pub leaderboard_id: ApplicationId<LeaderboardAbi>,
+    /// Bảng có tên của leaderboard (khu vực / sự kiện) mà các trận cũng được tính vào; None = chỉ bảng chính.
+    #[serde(default)]
+    pub board_id: Option<String>,
+    /// Khoá công khai của các game server được phép ký kết quả; rỗng = mọi kết quả đều bị từ chối.
+    #[serde(default)]
+    pub game_server_keys: Vec<Ed25519PublicKey>,
+    /// Signer được phép đổi khoá game server; rỗng = các owner của chain.
+    #[serde(default)]
+    pub admins: Vec<AccountOwner>,
 }
 
 pub struct XfighterAbi;
 
 impl ContractAbi for XfighterAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = XfighterResponse;
 }
 
 impl ServiceAbi for XfighterAbi {
     type Query = Request;
     type QueryResponse = Response;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchResultInput, RecordScoreResponse};
+    use linera_sdk::linera_base_types::{Ed25519SecretKey, Ed25519Signature};
+
+    fn input() -> MatchResultInput {
+        MatchResultInput {
+            match_id: "m1".to_string(),
+            player1_username: "alice".to_string(),
+            player2_username: "bob".to_string(),
+            winner_username: "alice".to_string(),
+            loser_username: "bob".to_string(),
+            duration_seconds: 300,
+            timestamp: 1_700_000_000,
+            player1_score: 3,
+            player2_score: 1,
+            map_name: "Arena".to_string(),
+            match_type: "Ranked".to_string(),
+            afk: None,
+            player1_hero: Some("Ryu".to_string()),
+            player2_hero: None,
+            signature: None,
+        }
+    }
+
+    /// Ký như game server: Ed25519 trên `payload()`, gửi dạng hex.
+    fn signed(secret: &Ed25519SecretKey) -> MatchResultInput {
+        let mut input = input();
+        let signature = Ed25519Signature::new(&input.payload(), secret);
+        input.signature = Some(hex::encode(signature.as_bytes()));
+        input
+    }
+
+    #[test]
+    fn signature_from_a_game_server_key_is_accepted() {
+        let secret = Ed25519SecretKey::generate();
+        let other = Ed25519SecretKey::generate();
+        let input = signed(&secret);
+        assert_eq!(input.verify_signature(&[secret.public()]), Ok(()));
+        assert_eq!(input.verify_signature(&[other.public(), secret.public()]), Ok(()));
+    }
+
+    #[test]
+    fn signature_from_another_key_is_rejected() {
+        let secret = Ed25519SecretKey::generate();
+        let other = Ed25519SecretKey::generate();
+        assert_eq!(signed(&other).verify_signature(&[secret.public()]), Err(RecordScoreResponse::InvalidSignature));
+    }
+
+    #[test]
+    fn changing_a_signed_field_invalidates_the_signature() {
+        let secret = Ed25519SecretKey::generate();
+        let mut input = signed(&secret);
+        input.match_id = "another-match".to_string();
+        assert_eq!(input.verify_signature(&[secret.public()]), Err(RecordScoreResponse::InvalidSignature));
+    }
+
+    #[test]
+    fn missing_or_malformed_signature_is_rejected() {
+        let key = Ed25519SecretKey::generate().public();
+        let mut input = input();
+        assert_eq!(input.verify_signature(&[key]), Err(RecordScoreResponse::MissingSignature));
+        input.signature = Some(String::new());
+        assert_eq!(input.verify_signature(&[key]), Err(RecordScoreResponse::MissingSignature));
+        input.signature = Some("not hex".to_string());
+        assert_eq!(input.verify_signature(&[key]), Err(RecordScoreResponse::InvalidSignature));
+        input.signature = Some("abcd".to_string());
+        assert_eq!(input.verify_signature(&[key]), Err(RecordScoreResponse::InvalidSignature));
+    }
+
+    #[test]
+    fn no_game_server_keys_rejects_every_result() {
+        let secret = Ed25519SecretKey::generate();
+        assert_eq!(input().verify_signature(&[]), Err(RecordScoreResponse::MissingSignature));
+        assert_eq!(signed(&secret).verify_signature(&[]), Err(RecordScoreResponse::InvalidSignature));
+    }
+}
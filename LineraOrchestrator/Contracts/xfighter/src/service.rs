@@ -10,8 +10,8 @@ use log::info;
 use std::sync::Arc;
 use self::state::{MatchResult, XfighterState}; 
 use async_graphql::{EmptySubscription, SimpleObject, Object, Request, Response, Schema};
-use linera_sdk::{linera_base_types::WithServiceAbi, views::View, Service, ServiceRuntime};
-use xfighter::{MatchResultInput, Operation, XfighterAbi, FactoryOperation}; //NEW PATCH Factory
+use linera_sdk::{linera_base_types::{Ed25519PublicKey, WithServiceAbi}, views::View, Service, ServiceRuntime};
+use xfighter::{MatchResultInput, Operation, Parameters, RecordScoreResponse, XfighterAbi, FactoryOperation}; //NEW PATCH Factory
 linera_sdk::service!(XfighterService);
 
 pub struct XfighterService {
@@ -24,7 +24,7 @@ impl WithServiceAbi for XfighterService {
 }
 
 impl Service for XfighterService {
-    type Parameters = ();
+    type Parameters = Parameters;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = XfighterState::load(runtime.root_view_storage_context())
@@ -39,7 +39,7 @@ impl Service for XfighterService {
     async fn handle_query(&self, request: Request) -> Response {
         let schema = Schema::build(
             QueryRoot { runtime: self.runtime.clone(), state: self.state.clone() },
-            MutationRoot { runtime: self.runtime.clone()},
+            MutationRoot { runtime: self.runtime.clone(), state: self.state.clone() },
             EmptySubscription,
         ).finish();
         schema.execute(request).await
@@ -48,6 +48,7 @@ impl Service for XfighterService {
 
 struct MutationRoot {
     runtime: Arc<ServiceRuntime<XfighterService>>,
+    state: Arc<XfighterState>,
 }
 
 
@@ -63,23 +64,55 @@ impl MutationRoot {
     }
 
     /// GraphQL mutation recordScore(matchResult) = client Unity.
+    /// Bị từ chối thì trả về lý do (INVALID_WINNER, DUPLICATE, ...) và không tạo operation;
+    /// hợp lệ thì trả về RECORDED và operation được lên lịch (contract kiểm tra lại đúng các điều kiện này).
     async fn record_score(
         &self,
          // Đặt tên tham số đúng `matchResult` để khớp payload client gửi
         #[graphql(name = "matchResult")] match_result: MatchResultInput,
-    ) -> bool {
-	// Kiểm tra hợp lệ của match_result
-	if match_result.winner_username != match_result.player1_username && match_result.winner_username != match_result.player2_username {
-            return false; // Trả về false nếu người thắng không hợp lệ
+    ) -> RecordScoreResponse {
+        // Kiểm tra hợp lệ của match_result
+        if let Err(rejection) = match_result.validate() {
+            return rejection;
+        }
+        if self.state.match_results.contains_key(&match_result.match_id).await.unwrap_or(false) {
+            return RecordScoreResponse::Duplicate;
+        }
+        if let Err(rejection) = match_result.verify_signature(&game_server_keys(&self.state, &self.runtime)) {
+            return rejection;
         }
         let op = Operation::RecordScore(match_result);
         self.runtime.schedule_operation(&op); // ServiceRuntime sẽ đóng gói Operation và gửi sang contract (BCS tự động).
+        RecordScoreResponse::Recorded
+    }
+
+    /// Thêm / bỏ khoá game server (hex) trên chain này (chỉ admin); trả về false nếu có khoá không hợp lệ
+    /// hoặc chain sẽ không còn khoá nào.
+    async fn rotate_game_server_keys(&self, add: Vec<String>, remove: Vec<String>) -> bool {
+        let parse = |keys: Vec<String>| {
+            keys.iter().map(|key| key.parse::<Ed25519PublicKey>()).collect::<Result<Vec<_>, _>>()
+        };
+        let (Ok(add), Ok(remove)) = (parse(add), parse(remove)) else {
+            return false;
+        };
+        let current = game_server_keys(&self.state, &self.runtime);
+        if add.is_empty() && current.iter().all(|key| remove.contains(key)) {
+            return false;
+        }
+        self.runtime.schedule_operation(&Operation::RotateGameServerKeys { add, remove });
         true
-    }	
+    }
+}
+
+/// Khoá game server đang được chấp nhận (giống contract).
+fn game_server_keys(state: &XfighterState, runtime: &ServiceRuntime<XfighterService>) -> Vec<Ed25519PublicKey> {
+    match state.game_server_keys.get() {
+        Some(keys) => keys.clone(),
+        None => runtime.application_parameters().game_server_keys,
+    }
 }
 
 struct QueryRoot {
-	#[allow(dead_code)]
     runtime: Arc<ServiceRuntime<XfighterService>>,
 	state: Arc<XfighterState>,
 }
@@ -102,6 +135,30 @@ impl QueryRoot {
         }
         results
     }
+    /// Các trận đã ghi trên chain này theo thứ tự ghi nhận (phân trang bằng offset / limit).
+    async fn matches(&self, offset: Option<u64>, limit: Option<u64>) -> Vec<MatchResult> {
+        let count = self.state.match_order.count();
+        let start = (offset.unwrap_or(0) as usize).min(count);
+        let end = limit.map_or(count, |limit| start.saturating_add(limit as usize).min(count));
+        let ids = self.state.match_order.read(start..end).await.unwrap_or_default();
+        let mut results = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(m) = self.state.match_results.get(&id).await.ok().flatten() {
+                results.push(m);
+            }
+        }
+        results
+    }
+
+    /// Số trận đã ghi trên chain này.
+    async fn match_count(&self) -> u64 {
+        self.state.match_order.count() as u64
+    }
+
+    async fn match_result(&self, match_id: String) -> Option<MatchResult> {
+        self.state.match_results.get(&match_id).await.ok().flatten()
+    }
+
 	///Get all new chain
     async fn all_opened_chains(&self) -> Vec<String> {
         let mut chains = Vec::new();
@@ -127,6 +184,11 @@ impl QueryRoot {
         }
         pairs
     }
+    /// Khoá game server (hex) đang được chấp nhận trên chain này.
+    async fn game_server_keys(&self) -> Vec<String> {
+        game_server_keys(&self.state, &self.runtime).iter().map(|key| key.to_string()).collect()
+    }
+
 	/// Get leaderboard id for debug
 	async fn leaderboard_id(&self) -> Option<String> {
         let state_id = self.state.leaderboard_id.get();
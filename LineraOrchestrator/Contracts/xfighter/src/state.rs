@@ -3,8 +3,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use linera_sdk::{
-    linera_base_types::{ApplicationId, ChainId},
-    views::{MapView, RegisterView, RootView, ViewStorageContext, SetView},
+    linera_base_types::{ApplicationId, ChainId, Ed25519PublicKey},
+    views::{LogView, MapView, RegisterView, RootView, ViewStorageContext, SetView},
 };
 use serde::{Deserialize, Serialize};
 use async_graphql::SimpleObject;
@@ -27,15 +27,19 @@ pub struct MatchResult {
     pub map_name: String,
     pub match_type: String,
 	pub afk: Option<String>,
+    pub player1_hero: Option<String>,
+    pub player2_hero: Option<String>,
 }
 
 /// State của Xfighter
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct XfighterState {
-    pub match_results: MapView<String, MatchResult>,
+    pub match_results: MapView<String, MatchResult>, // match_id -> kết quả; một chain có thể ghi nhiều trận
+    pub match_order: LogView<String>, // match_id theo thứ tự ghi nhận trên chain này
     pub leaderboard_id: RegisterView<Option<ApplicationId<LeaderboardAbi>>>,
     pub opened_chains: SetView<ChainId>,
     pub child_apps: MapView<ChainId, ApplicationId<XfighterAbi>>,
-	pub sent_messages: MapView<String, bool>, //flag check duplication sent_messages
+	pub sent_messages: MapView<String, bool>, // match_id -> đã gửi MatchOutcome (chống gửi trùng)
+    pub game_server_keys: RegisterView<Option<Vec<Ed25519PublicKey>>>, // None = dùng Parameters::game_server_keys
 }
\ No newline at end of file
@@ -1,37 +1,325 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-//! Integration tests for the Counter application.
+//! Integration tests for the XFighter application.
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use linera_sdk::test::{QueryOutcome, TestValidator};
+use std::str::FromStr;
 
-/// Test setting a counter and testing its coherency across microchains.
-///
-/// Creates the application on a `chain`, initializing it with a 42 then adds 15 and obtains 57.
-/// which is then checked.
-#[tokio::test(flavor = "multi_thread")]
-async fn single_chain_test() {
-    let (validator, module_id) =
-        TestValidator::with_current_module::<counter::CounterAbi, (), u64>().await;
+use assert_matches::assert_matches;
+use leaderboard::{LeaderboardAbi, LeaderboardConfig};
+use linera_sdk::{
+    linera_base_types::{
+        AccountOwner, ApplicationId, CryptoHash, Ed25519PublicKey, Ed25519SecretKey, Ed25519Signature,
+    },
+    test::{ActiveChain, QueryOutcome, TestValidator},
+};
+use xfighter::{
+    FactoryOperation, MatchResultInput, Operation, Parameters, RecordScoreResponse, XfighterAbi, XfighterResponse,
+};
+
+/// Leaderboard, factory xfighter (được owner của chain authorize) và một app con do factory tạo qua `OpenAndCreate`.
+/// App con nhận `game_server_key` làm khoá game server duy nhất.
+async fn factory_with_child(game_server_key: Ed25519PublicKey) -> (
+    ActiveChain,
+    ApplicationId<LeaderboardAbi>,
+    ApplicationId<XfighterAbi>,
+    ApplicationId<XfighterAbi>,
+) {
+    let (validator, module_id) = TestValidator::with_current_module::<XfighterAbi, Parameters, ()>().await;
     let mut chain = validator.new_chain().await;
 
-    let initial_state = 42u64;
-    let application_id = chain
-        .create_application(module_id, (), initial_state, vec![])
+    let leaderboard_module = chain
+        .publish_bytecode_files_in::<LeaderboardAbi, leaderboard::Parameters, Option<LeaderboardConfig>>(
+            "../leaderboard",
+        )
+        .await;
+    let leaderboard_id = chain
+        .create_application(leaderboard_module, leaderboard::Parameters::default(), None, vec![])
+        .await;
+
+    let parameters = Parameters {
+        xfighter_module: module_id.forget_abi(),
+        leaderboard_id,
+        board_id: None,
+        game_server_keys: vec![game_server_key],
+        admins: Vec::new(),
+    };
+    let factory_id = chain.create_application(module_id, parameters, (), vec![]).await;
+
+    // Owner của chain là admin của leaderboard (Parameters::admins rỗng).
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                leaderboard_id,
+                leaderboard::Operation::AuthorizeApplication { application_id: factory_id.forget_abi() },
+            );
+        })
+        .await;
+    chain
+        .add_block(|block| {
+            block.with_operation(factory_id, Operation::Factory(FactoryOperation::OpenAndCreate));
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain.graphql_query(factory_id, "query { allChildApps { appId } }").await;
+    let child_hash = response["allChildApps"][0]["appId"].as_str().expect("Failed to get the child app");
+    let child_id = ApplicationId::new(CryptoHash::from_str(child_hash).expect("Invalid child app id"))
+        .with_abi::<XfighterAbi>();
+    assert_ne!(child_id, factory_id);
+
+    (chain, leaderboard_id, factory_id, child_id)
+}
+
+/// Kết quả `winner` thắng `loser`, chưa ký.
+fn match_result(match_id: &str, winner: &str, loser: &str) -> MatchResultInput {
+    MatchResultInput {
+        match_id: match_id.to_string(),
+        player1_username: winner.to_string(),
+        player2_username: loser.to_string(),
+        winner_username: winner.to_string(),
+        loser_username: loser.to_string(),
+        duration_seconds: 300,
+        timestamp: 0,
+        player1_score: 3,
+        player2_score: 1,
+        map_name: "Arena".to_string(),
+        match_type: "Ranked".to_string(),
+        afk: None,
+        player1_hero: None,
+        player2_hero: None,
+        signature: None,
+    }
+}
+
+/// Ký như game server: Ed25519 trên `payload()`, gửi dạng hex.
+fn signed(mut input: MatchResultInput, secret: &Ed25519SecretKey) -> MatchResultInput {
+    let signature = Ed25519Signature::new(&input.payload(), secret);
+    input.signature = Some(hex::encode(signature.as_bytes()));
+    input
+}
+
+/// Ghi các operation trong một block, trả về phản hồi (BCS) của từng operation theo thứ tự.
+async fn responses(
+    chain: &ActiveChain,
+    application_id: ApplicationId<XfighterAbi>,
+    operations: Vec<Operation>,
+) -> Vec<XfighterResponse> {
+    let certificate = chain
+        .add_block(|block| {
+            for operation in operations {
+                block.with_operation(application_id, operation);
+            }
+        })
+        .await;
+    certificate
+        .inner()
+        .block()
+        .body
+        .operation_results
+        .iter()
+        .map(|result| bcs::from_bytes(&result.0).expect("Failed to decode an operation response"))
+        .collect()
+}
+
+/// Kết quả ghi qua app con (do factory tạo trong `OpenAndCreate`) phải tới được leaderboard:
+/// orchestrator chỉ cấp quyền cho factory, factory tự cấp quyền cho app con.
+#[tokio::test(flavor = "multi_thread")]
+async fn child_app_results_reach_the_leaderboard() {
+    let secret = Ed25519SecretKey::generate();
+    let (chain, leaderboard_id, _factory_id, child_id) = factory_with_child(secret.public()).await;
+
+    let result = signed(match_result("m1", "alice", "bob"), &secret);
+    chain
+        .add_block(|block| {
+            block.with_operation(child_id, Operation::RecordScore(result));
+        })
+        .await;
+    // MatchOutcome được gửi về publisher chain (chain này), app con chuyển tiếp cho leaderboard.
+    chain.handle_received_messages().await;
+
+    let query = "query { leaderboard { userId totalWins totalLosses } rejectedWrites { action } writerApplications }";
+    let QueryOutcome { response, .. } = chain.graphql_query(leaderboard_id, query).await;
+    assert_eq!(response["rejectedWrites"].as_array().map(Vec::len), Some(0));
+    assert_eq!(response["writerApplications"].as_array().map(Vec::len), Some(2));
+    assert_eq!(response["leaderboard"][0]["userId"], "alice");
+    assert_eq!(response["leaderboard"][0]["totalWins"].as_u64(), Some(1));
+    assert_eq!(response["leaderboard"][1]["userId"], "bob");
+    assert_eq!(response["leaderboard"][1]["totalLosses"].as_u64(), Some(1));
+}
+
+/// Kết quả chưa ký hoặc ký bằng khoá khác không được lưu trên chain và không tới leaderboard.
+#[tokio::test(flavor = "multi_thread")]
+async fn unsigned_or_wrongly_signed_results_never_reach_the_leaderboard() {
+    let secret = Ed25519SecretKey::generate();
+    let (chain, leaderboard_id, _factory_id, child_id) = factory_with_child(secret.public()).await;
+
+    let unsigned = match_result("m1", "carol", "dave");
+    let wrongly_signed = signed(match_result("m2", "erin", "frank"), &Ed25519SecretKey::generate());
+    let tampered = {
+        let mut result = signed(match_result("m3", "alice", "bob"), &secret);
+        result.winner_username = "bob".to_string();
+        result.loser_username = "alice".to_string();
+        result
+    };
+    let accepted = signed(match_result("m4", "alice", "bob"), &secret);
+    chain
+        .add_block(|block| {
+            for result in [unsigned, wrongly_signed, tampered, accepted] {
+                block.with_operation(child_id, Operation::RecordScore(result));
+            }
+        })
         .await;
+    chain.handle_received_messages().await;
+
+    let QueryOutcome { response, .. } =
+        chain.graphql_query(child_id, "query { allMatchResults { matchId } }").await;
+    assert_eq!(response["allMatchResults"].as_array().map(Vec::len), Some(1));
+    assert_eq!(response["allMatchResults"][0]["matchId"], "m4");
+
+    let QueryOutcome { response, .. } =
+        chain.graphql_query(leaderboard_id, "query { leaderboard { userId totalWins totalLosses } }").await;
+    let leaderboard = response["leaderboard"].as_array().expect("Failed to get the leaderboard");
+    assert_eq!(leaderboard.len(), 2);
+    assert_eq!(leaderboard[0]["userId"], "alice");
+    assert_eq!(leaderboard[0]["totalWins"].as_u64(), Some(1));
+    assert_eq!(leaderboard[1]["userId"], "bob");
+    assert_eq!(leaderboard[1]["totalLosses"].as_u64(), Some(1));
+}
+
+/// App con là writer app nhưng không phải factory: `AuthorizeApplication` nó gửi (qua `OpenAndCreate` của chính nó)
+/// bị từ chối dù block được ký bởi owner của chain.
+#[tokio::test(flavor = "multi_thread")]
+async fn child_app_cannot_authorize_other_applications() {
+    let (chain, leaderboard_id, _factory_id, child_id) = factory_with_child(Ed25519SecretKey::generate().public()).await;
 
-    let increment = 15u64;
     chain
         .add_block(|block| {
-            block.with_operation(application_id, increment);
+            block.with_operation(child_id, Operation::Factory(FactoryOperation::OpenAndCreate));
         })
         .await;
 
-    let final_value = initial_state + increment;
+    let query = "query { rejectedWrites { action } writerApplications }";
+    let QueryOutcome { response, .. } = chain.graphql_query(leaderboard_id, query).await;
+    assert_eq!(response["writerApplications"].as_array().map(Vec::len), Some(2));
+    let rejected = response["rejectedWrites"].as_array().expect("Failed to get rejected writes");
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0]["action"], "AuthorizeApplication");
+}
+
+/// Một chain chứa nhiều trận: mỗi trận lưu riêng theo `match_id`, `matches(offset, limit)` phân trang theo thứ tự ghi.
+#[tokio::test(flavor = "multi_thread")]
+async fn one_chain_records_several_matches_in_order() {
+    let secret = Ed25519SecretKey::generate();
+    let (chain, leaderboard_id, _factory_id, child_id) = factory_with_child(secret.public()).await;
+
+    let results = [("m1", "alice", "bob"), ("m2", "bob", "carol"), ("m3", "carol", "alice"), ("m4", "alice", "carol")]
+        .map(|(match_id, winner, loser)| signed(match_result(match_id, winner, loser), &secret));
+    for result in results {
+        chain
+            .add_block(|block| {
+                block.with_operation(child_id, Operation::RecordScore(result));
+            })
+            .await;
+    }
+    chain.handle_received_messages().await;
+
+    let query = "query { matchCount allMatchResults { matchId } \
+                 page: matches(offset: 1, limit: 2) { matchId winnerUsername } \
+                 tail: matches(offset: 3, limit: 10) { matchId } \
+                 past: matches(offset: 9) { matchId } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(child_id, query).await;
+    assert_eq!(response["matchCount"].as_u64(), Some(4));
+    assert_eq!(response["allMatchResults"].as_array().map(Vec::len), Some(4));
+    assert_eq!(
+        response["page"],
+        serde_json::json!([
+            { "matchId": "m2", "winnerUsername": "bob" },
+            { "matchId": "m3", "winnerUsername": "carol" },
+        ])
+    );
+    assert_eq!(response["tail"], serde_json::json!([{ "matchId": "m4" }]));
+    assert_eq!(response["past"], serde_json::json!([]));
+
     let QueryOutcome { response, .. } =
-        chain.graphql_query(application_id, "query { value }").await;
-    let state_value = response["value"].as_u64().expect("Failed to get the u64");
-    assert_eq!(state_value, final_value);
+        chain.graphql_query(leaderboard_id, "query { leaderboard { userId totalWins totalLosses } }").await;
+    let leaderboard = response["leaderboard"].as_array().expect("Failed to get the leaderboard");
+    assert_eq!(leaderboard.len(), 3);
+    assert_eq!(leaderboard[0]["userId"], "alice");
+    assert_eq!(leaderboard[0]["totalWins"].as_u64(), Some(2));
+}
+
+/// Mỗi nhánh của `RecordScore` / `RotateGameServerKeys` trả về đúng `XfighterResponse` của nó.
+#[tokio::test(flavor = "multi_thread")]
+async fn operations_report_why_they_were_rejected() {
+    let secret = Ed25519SecretKey::generate();
+    let (chain, _leaderboard_id, _factory_id, child_id) = factory_with_child(secret.public()).await;
+
+    let with = |edit: fn(&mut MatchResultInput)| {
+        let mut result = match_result("m2", "alice", "bob");
+        edit(&mut result);
+        Operation::RecordScore(signed(result, &secret))
+    };
+    let operations = vec![
+        Operation::RecordScore(signed(match_result("m1", "alice", "bob"), &secret)),
+        Operation::RecordScore(signed(match_result("m1", "alice", "bob"), &secret)),
+        with(|result| result.match_id = " ".to_string()),
+        with(|result| result.player2_username = "alice".to_string()),
+        with(|result| result.winner_username = "carol".to_string()),
+        with(|result| result.loser_username = "alice".to_string()),
+        Operation::RecordScore(match_result("m2", "alice", "bob")),
+        Operation::RecordScore(signed(match_result("m2", "alice", "bob"), &Ed25519SecretKey::generate())),
+        Operation::RotateGameServerKeys { add: Vec::new(), remove: vec![secret.public()] },
+        Operation::RotateGameServerKeys { add: vec![Ed25519SecretKey::generate().public()], remove: Vec::new() },
+    ];
+    let responses = responses(&chain, child_id, operations).await;
+    assert_eq!(responses.len(), 10);
+    let record_score = [
+        RecordScoreResponse::Recorded,
+        RecordScoreResponse::Duplicate,
+        RecordScoreResponse::InvalidMatchId,
+        RecordScoreResponse::InvalidPlayers,
+        RecordScoreResponse::InvalidWinner,
+        RecordScoreResponse::InvalidLoser,
+        RecordScoreResponse::MissingSignature,
+        RecordScoreResponse::InvalidSignature,
+    ];
+    for (response, expected) in responses.iter().zip(record_score) {
+        assert_matches!(response, XfighterResponse::RecordScore(reason) if *reason == expected);
+    }
+    assert_matches!(responses[8], XfighterResponse::NoGameServerKeys);
+    assert_matches!(responses[9], XfighterResponse::Ok);
+}
+
+/// Signer không nằm trong `Parameters::admins` không đổi được khoá game server.
+#[tokio::test(flavor = "multi_thread")]
+async fn non_admins_cannot_rotate_game_server_keys() {
+    let (validator, module_id) = TestValidator::with_current_module::<XfighterAbi, Parameters, ()>().await;
+    let mut chain = validator.new_chain().await;
+    let admin = validator.new_chain().await;
+
+    let leaderboard_module = chain
+        .publish_bytecode_files_in::<LeaderboardAbi, leaderboard::Parameters, Option<LeaderboardConfig>>(
+            "../leaderboard",
+        )
+        .await;
+    let leaderboard_id = chain
+        .create_application(leaderboard_module, leaderboard::Parameters::default(), None, vec![])
+        .await;
+    let parameters = Parameters {
+        xfighter_module: module_id.forget_abi(),
+        leaderboard_id,
+        board_id: None,
+        game_server_keys: vec![Ed25519SecretKey::generate().public()],
+        admins: vec![AccountOwner::from(admin.public_key())],
+    };
+    let application_id = chain.create_application(module_id, parameters, (), vec![]).await;
+
+    let rotate = Operation::RotateGameServerKeys {
+        add: vec![Ed25519SecretKey::generate().public()],
+        remove: Vec::new(),
+    };
+    let responses = responses(&chain, application_id, vec![rotate]).await;
+    assert_matches!(responses[..], [XfighterResponse::Unauthorized]);
 }
@@ -71,6 +71,14 @@ namespace LineraOrchestrator.Models
         [JsonPropertyName("matchType")] public string? MatchType { get; set; }
 
         [JsonPropertyName("afk")] public string? Afk { get; set; }
+
+        [JsonPropertyName("player1Hero")] public string? Player1Hero { get; set; }
+
+        [JsonPropertyName("player2Hero")] public string? Player2Hero { get; set; }
+
+        // Chữ ký Ed25519 (hex) của game server trên kết quả trận, chuyển nguyên cho xfighter kiểm tra.
+        // Chữ ký phủ mọi trường ở trên (kể cả MatchId): orchestrator không được sửa các trường này trước khi submit.
+        [JsonPropertyName("signature")] public string? Signature { get; set; }
     }
     public class MatchMapping
     {
@@ -29,6 +29,10 @@ namespace LineraOrchestrator.Models
         public string? LeaderboardAppId { get; set; }
         public string? TournamentAppId { get; set; }
 
+        // Khoá công khai Ed25519 (hex) của game server, truyền vào game_server_keys của XFighter factory.
+        // Không có khoá nào thì XFighter từ chối mọi kết quả trận.
+        public List<string> GameServerKeys { get; set; } = new();
+
         // Ready check
         public bool IsReady => !string.IsNullOrEmpty(PublisherChainId)
                        && !string.IsNullOrEmpty(XFighterModuleId)
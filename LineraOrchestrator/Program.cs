@@ -25,6 +25,11 @@ var lineraConfig = new LineraConfig
     UseRemoteTestnet = true,          // true Setup Node CONWAY mode, false Setup Node Backup mode
     StartServiceWhenRemote = true,   // true Setup Service CONWAY mode, false Setup Service Backup mode
     FaucetUrl = "https://faucet.testnet-conway.linera.net",
+
+    // Khoá game server (hex, phân cách bằng dấu phẩy), ví dụ GAME_SERVER_KEYS=ab12...,cd34...
+    GameServerKeys = (Environment.GetEnvironmentVariable("GAME_SERVER_KEYS") ?? "")
+        .Split(',', StringSplitOptions.RemoveEmptyEntries | StringSplitOptions.TrimEntries)
+        .ToList(),
 };
 
 builder.Services.AddSingleton(lineraConfig);
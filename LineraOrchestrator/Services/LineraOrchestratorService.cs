@@ -21,6 +21,11 @@ namespace LineraOrchestrator.Services
         private static readonly Dictionary<string, MatchMapping> _matchMap = new(StringComparer.OrdinalIgnoreCase);
         private static readonly string _matchMappingFile = Path.Combine(ROOT, "match_mapping.json");
 
+        // Trạng thái submit theo từng trận (key = matchId), tách khỏi _matchMap (key = chainId/requestId)
+        // để matchId trùng chainId không ghi đè mapping của chain.
+        private static readonly Dictionary<string, MatchMapping> _matchStatus = new(StringComparer.OrdinalIgnoreCase);
+        private static readonly string _matchStatusFile = Path.Combine(ROOT, "match_status.json");
+
         // Logic Searching Onchain PlayerIndex
         private static readonly Dictionary<string, PlayerStats> _playerIndex = new(StringComparer.OrdinalIgnoreCase);
         private static readonly string _playerIndexFile = Path.Combine(ROOT, "player_index.json");
@@ -29,6 +34,13 @@ namespace LineraOrchestrator.Services
         private static readonly object _submitFileLock = new();
         private static readonly string _submitRequestsFile = Path.Combine(ROOT, "submit_requests.json");
 
+        // Lý do từ chối mà mutation recordScore của xfighter có thể trả về (RecordScoreResponse)
+        private static readonly HashSet<string> RecordScoreRejections = new(StringComparer.OrdinalIgnoreCase)
+        {
+            "DUPLICATE", "INVALID_MATCH_ID", "INVALID_PLAYERS", "INVALID_WINNER", "INVALID_LOSER",
+            "MISSING_SIGNATURE", "INVALID_SIGNATURE"
+        };
+
         // SAVE - LOAD - ADD Snapshot Leaderboard for Tournament
         private static readonly string _snapshotFile = Path.Combine(ROOT, "snapshot_leaderboard.json");
         private static readonly object _snapshotLock = new();
@@ -88,6 +100,7 @@ namespace LineraOrchestrator.Services
             Console.WriteLine($"[CONFIG] Storage: {_config.LineraStorage}");
 
             LoadMatchMapping(); // Tải dữ liệu danh sách Match khi khởi động
+            LoadMatchStatus();
             LoadPlayerIndex(); // Tải dữ liệu danh sách Player khi khởi động
 
             // --- Dọn rác khởi đầu, Gom dọn rác cũ hoặc bị bỏ sót ---
@@ -194,6 +207,9 @@ namespace LineraOrchestrator.Services
                     await Task.Delay(1000); // give service a moment to initialize and register blob-store client
                     StartServiceMonitor(); // Start monitor (watchdog/supervisor) to ensure service stays up
                     Console.WriteLine("[SERVICE] Linera service started and monitored.");
+
+                    //9. Cho phép XFighter ghi điểm vào Leaderboard
+                    await RetryAsync(() => AuthorizeXfighterOnLeaderboardAsync());
                 }
                 else
                 {
@@ -242,7 +258,9 @@ namespace LineraOrchestrator.Services
                 servicePath,
                 _config.PublisherChainId!,
                 "--json-argument",
-                "null"
+                "null",
+                "--json-parameters",
+                "{}" // xfighter được cấp quyền ghi điểm sau khi tạo (AuthorizeXfighterOnLeaderboardAsync)
             );
 
             if (string.IsNullOrWhiteSpace(result))
@@ -254,22 +272,55 @@ namespace LineraOrchestrator.Services
             Console.WriteLine($"Successfully created Leaderboard app with ID: {leaderboardAppId}");
             return leaderboardAppId;
         }
+        // Leaderboard chỉ nhận điểm từ ứng dụng được cấp quyền -> authorize XFighter factory (chạy trên publisher chain).
+        // App con tạo bởi OpenAndCreate được factory tự cấp quyền, không cần gọi lại ở đây.
+        public async Task<string> AuthorizeXfighterOnLeaderboardAsync()
+        {
+            var url = $"http://localhost:8080/chains/{_config.PublisherChainId}/applications/{_config.LeaderboardAppId}";
+            var payload = new
+            {
+                query = "mutation authorize($appId: ApplicationId!) { authorizeApplication(applicationId: $appId) }",
+                variables = new { appId = _config.XFighterAppId }
+            };
+
+            var resp = await PostSingleWithServiceWaitAsync(
+                url,
+                () => new StringContent(
+                    JsonSerializer.Serialize(payload, JsonOptions.Write), Encoding.UTF8, "application/json"),
+                waitSeconds: 10,
+                postTimeoutSeconds: 30).ConfigureAwait(false);
+
+            var text = await resp.Content.ReadAsStringAsync().ConfigureAwait(false);
+            if (!resp.IsSuccessStatusCode || text.Contains("\"errors\""))
+                throw new InvalidOperationException($"Failed to authorize XFighter on leaderboard: {text}");
+
+            Console.WriteLine($"Authorized XFighter app {_config.XFighterAppId} on leaderboard {_config.LeaderboardAppId}");
+            return text;
+        }
         // Phương thức sử dụng publish-and-create tạo XFighter Factory
         public async Task<string> DeployXfighterFactoryAsync()
         {
             var contractPath = Path.Combine(_config.XFighterPath, "xfighter_contract.wasm");
             var servicePath = Path.Combine(_config.XFighterPath, "xfighter_service.wasm");
 
+            // XFighter từ chối mọi kết quả khi không có khoá game server -> dừng sớm thay vì deploy factory vô dụng.
+            if (_config.GameServerKeys.Count == 0)
+                throw new InvalidOperationException("No game server keys configured (GAME_SERVER_KEYS).");
+
+            var parameters = JsonSerializer.Serialize(new Dictionary<string, object?>
+            {
+                ["xfighter_module"] = _config.XFighterModuleId,
+                ["leaderboard_id"] = _config.LeaderboardAppId,
+                ["game_server_keys"] = _config.GameServerKeys,
+            });
+
             var result = await _cli.RunAndCaptureOutputAsync(
                 "publish-and-create",
                 contractPath,
                 servicePath,
                 _config.PublisherChainId!,
                 "--json-argument", "null",
-                "--json-parameters",
-                    $"{{\"xfighter_module\":\"{_config.XFighterModuleId}\"," +
-                      $"\"leaderboard_id\":\"{_config.LeaderboardAppId}\"}}"
-
+                "--json-parameters", parameters
             );
 
             if (string.IsNullOrWhiteSpace(result))
@@ -862,43 +913,55 @@ namespace LineraOrchestrator.Services
             if (string.IsNullOrWhiteSpace(chainId))
                 throw new ArgumentNullException(nameof(chainId));
             ArgumentNullException.ThrowIfNull(matchResult);
+            // MatchId nằm trong payload đã ký (game server ký) và là khoá chống trùng của contract: không được ghi đè.
+            if (string.IsNullOrWhiteSpace(matchResult.MatchId))
+                throw new ArgumentException("matchResult.MatchId is required.", nameof(matchResult));
 
-            // Đảm bảo matchId = chainId để nhất quán
-            matchResult.MatchId = chainId;
-
+            // Một chain có thể chứa nhiều trận: mapping của chain (_matchMap, key = chainId) chỉ dùng để lấy AppId,
+            // trạng thái submit lưu theo từng trận trong _matchStatus (key = matchId).
+            var matchKey = matchResult.MatchId;
             string appId;
-            bool needSave = false;
             lock (_matchMap)
             {
-                if (!_matchMap.TryGetValue(chainId, out var existing))
+                if (!_matchMap.TryGetValue(chainId, out var chainMapping))
                     throw new InvalidOperationException(
                         $"No mapping found for chain {chainId}. use open and create chain before submit .");
 
-                if (string.Equals(existing.Status, "submitted", StringComparison.OrdinalIgnoreCase))
-                    throw new InvalidOperationException(
-                        $"Match on chain {chainId} has already been submitted (status=submitted).");
-
-                if (string.IsNullOrWhiteSpace(existing.AppId))
+                if (string.IsNullOrWhiteSpace(chainMapping.AppId))
                     throw new InvalidOperationException(
                         $"Mapping for chain {chainId} missing AppId. Sai flow open-and-create.");
 
-                if (string.Equals(existing.Status, "creating", StringComparison.OrdinalIgnoreCase))
+                if (string.Equals(chainMapping.Status, "creating", StringComparison.OrdinalIgnoreCase))
                     throw new InvalidOperationException(
                         $"Match {chainId} is still creating. Please retry later.");
 
-                appId = existing.AppId;
+                appId = chainMapping.AppId;
+            }
+
+            lock (_matchStatus)
+            {
+                // Prevent duplicate submission: atomic check & set theo matchId
+                if (_matchStatus.TryGetValue(matchKey, out var existing) &&
+                    string.Equals(existing.Status, "submitted", StringComparison.OrdinalIgnoreCase))
+                    throw new InvalidOperationException(
+                        $"Match {matchKey} has already been submitted (status=submitted).");
 
-                // update trạng thái, KHÔNG ghi đè AppId
-                existing.MatchId = matchResult.MatchId;
-                existing.Status = "submitting";
-                existing.SubmittedAt = DateTime.UtcNow.ToString("s");
-                needSave = true;
+                // MatchId giữ nguyên như game server gửi
+                _matchStatus[matchKey] = new MatchMapping
+                {
+                    MatchId = matchKey,
+                    ChainId = chainId,
+                    AppId = appId,
+                    Player1 = matchResult.Player1Username,
+                    Player2 = matchResult.Player2Username,
+                    Status = "submitting",
+                    SubmittedAt = DateTime.UtcNow.ToString("s")
+                };
             }
-            if (needSave) SaveMatchMapping();
+            SaveMatchStatus();
 
-            // Prevent duplicate submission: atomic check & set using lock on _matchMap
-            var matchKey = chainId; // dùng chainId làm key duy nhất
             string? opHex = null;
+            string? rejection = null;
             string text = string.Empty;
             var url = $"http://localhost:8080/chains/{chainId}/applications/{appId}";
             var graphql = @"
@@ -929,7 +992,7 @@ namespace LineraOrchestrator.Services
                 // 1) HTTP non-2xx -> fail
                 if (!resp.IsSuccessStatusCode)
                 {
-                    UpdateMatchStatus(chainId, "submit failed");
+                    UpdateSubmitStatus(matchKey, "submit failed");
 
                     Console.WriteLine($"[WARN] HTTP {resp.StatusCode}: {text}");
                     Console.WriteLine("[INFO] Waiting for Linera service to recover before continuing...");
@@ -939,7 +1002,7 @@ namespace LineraOrchestrator.Services
             }
             catch (OperationCanceledException)
             {
-                UpdateMatchStatus(chainId, "submit failed");
+                UpdateSubmitStatus(matchKey, "submit failed");
 
                 // Pause the queue until monitor reports service stable (same behavior as open-and-create)
                 Console.WriteLine("[INFO] Submit failed due to timeout. Waiting for Linera service to recover before continuing...");
@@ -949,7 +1012,7 @@ namespace LineraOrchestrator.Services
             catch (Exception ex)
             {
                 // mark mapping as failed if relevant
-                UpdateMatchStatus(chainId, "submit failed");
+                UpdateSubmitStatus(matchKey, "submit failed");
 
                 // Pause the queue until monitor reports service stable (same behavior as open-and-create)
                 Console.WriteLine($"[INFO] Submit failed: {ex.Message}. Waiting for Linera service to recover before continuing...");
@@ -971,7 +1034,11 @@ namespace LineraOrchestrator.Services
                              dataEl.TryGetProperty("recordScore", out var rs) &&
                              rs.ValueKind == JsonValueKind.String)
                     {
-                        opHex = rs.GetString(); // trường hợp Linera trả về object { recordScore: "..."}
+                        var value = rs.GetString();
+                        if (value != null && RecordScoreRejections.Contains(value))
+                            rejection = value; // contract không được gọi: trả về lý do từ chối
+                        else
+                            opHex = value; // trường hợp Linera trả về object { recordScore: "..."}
                     }
                 }
             }
@@ -979,19 +1046,33 @@ namespace LineraOrchestrator.Services
             {
                 Console.WriteLine($"[WARN] Parse op hex failed: {ex.Message}");
             }
+            if (rejection != null)
+            {
+                UpdateSubmitStatus(matchKey, "submit rejected");
+                Console.WriteLine($"[WARN] recordScore rejected for chain={chainId}: {rejection}");
+                return JsonSerializer.Serialize(new
+                {
+                    success = false,
+                    matchId = matchResult.MatchId,
+                    chainId,
+                    reason = rejection,
+                    raw = text
+                });
+            }
+
             Console.WriteLine($"[DEBUG] Successfully Submitted match on chainId {chainId}.");
             Console.WriteLine($"[DEBUG] Extracted opHex = {opHex ?? "null"}");
             // Kiểm tra appId từ mapping
-            if (_matchMap.TryGetValue(matchKey, out var current))
+            if (_matchStatus.TryGetValue(matchKey, out var current))
             {
                 Console.WriteLine($"[DEBUG] Current mapping before update: appId={current.AppId ?? "null"}, status={current.Status}");
             }
 
             bool needSave2 = false;
             // Update mapping submitted
-            lock (_matchMap)
+            lock (_matchStatus)
             {
-                if (_matchMap.TryGetValue(matchKey, out var m))
+                if (_matchStatus.TryGetValue(matchKey, out var m))
                 {
                     m.Status = "submitted";
                     m.SubmittedOpId = string.IsNullOrWhiteSpace(opHex) ? null : opHex;
@@ -999,7 +1080,7 @@ namespace LineraOrchestrator.Services
                     needSave2 = true;
                 }
             }
-            if (needSave2) SaveMatchMapping();
+            if (needSave2) SaveMatchStatus();
 
             // Tracking History Update player index cho cả 2 player
             AddPlayerIndex(
@@ -1057,6 +1138,25 @@ namespace LineraOrchestrator.Services
                 SaveMatchMapping();
             }
         }
+        // Helper: update trạng thái submit của một trận (_matchStatus)
+        private static void UpdateSubmitStatus(string matchKey, string status)
+        {
+            if (string.IsNullOrWhiteSpace(matchKey)) return;
+            bool needSave = false;
+            lock (_matchStatus)
+            {
+                if (_matchStatus.TryGetValue(matchKey, out var m))
+                {
+                    m.Status = status;
+                    m.SubmittedAt = DateTime.UtcNow.ToString("s");
+                    needSave = true;
+                }
+            }
+            if (needSave)
+            {
+                SaveMatchStatus();
+            }
+        }
         // Helper: DEBUG wait leaderboard confirm after submit
         private async Task<bool> WaitForLeaderboardUpdateAsync(string player1, string player2, int timeoutMs = 8000, int pollIntervalMs = 1000)
         {
@@ -1253,6 +1353,67 @@ namespace LineraOrchestrator.Services
             }
         }
 
+        // Trạng thái submit theo trận: file riêng, key = matchId (không đoán lại key khi load).
+        private static bool SaveMatchStatus()
+        {
+            try
+            {
+                lock (_matchStatus)
+                {
+                    var json = JsonSerializer.Serialize(_matchStatus, JsonOptions.Write);
+
+                    var dir = Path.GetDirectoryName(_matchStatusFile);
+                    if (!string.IsNullOrWhiteSpace(dir) && !Directory.Exists(dir))
+                        Directory.CreateDirectory(dir);
+
+                    var tmp = _matchStatusFile + ".tmp";
+                    File.WriteAllText(tmp, json);
+
+                    if (File.Exists(_matchStatusFile))
+                        File.Replace(tmp, _matchStatusFile, null); // atomic replace
+                    else
+                        File.Move(tmp, _matchStatusFile); // first time
+
+                    return true;
+                }
+            }
+            catch (Exception ex)
+            {
+                Console.WriteLine($"[WARN] Failed to save match status: {ex.Message}");
+                return false;
+            }
+        }
+        private static void LoadMatchStatus()
+        {
+            lock (_matchStatus)
+            {
+                _matchStatus.Clear();
+                try
+                {
+                    if (!File.Exists(_matchStatusFile) || new FileInfo(_matchStatusFile).Length == 0)
+                    {
+                        Console.WriteLine("[INFO] Match status file not found or empty. Initializing empty map.");
+                        return;
+                    }
+
+                    var json = File.ReadAllText(_matchStatusFile);
+                    var data = JsonSerializer.Deserialize<Dictionary<string, MatchMapping>>(json, JsonOptions.Read);
+                    if (data != null)
+                    {
+                        foreach (var kv in data)
+                            _matchStatus[kv.Key] = kv.Value;
+                    }
+
+                    Console.WriteLine($"[INFO] Successfully loaded {_matchStatus.Count} match statuses.");
+                }
+                catch (Exception ex)
+                {
+                    File.Copy(_matchStatusFile, _matchStatusFile + ".bak", overwrite: true); // Lưu vào file hỏng để debug
+                    Console.WriteLine($"[WARN] Failed to load match status: {ex.Message}");
+                    _matchStatus.Clear();
+                }
+            }
+        }
 
         //DEBUG
         public MatchMapping? GetMappingForChain(string chainId)
@@ -1266,6 +1427,12 @@ namespace LineraOrchestrator.Services
             // return a copy to avoid external modification
             lock (_matchMap) return new Dictionary<string, MatchMapping>(_matchMap);
         }
+        //DEBUG: trạng thái submit của một trận (key = matchId)
+        public MatchMapping? GetMatchStatus(string matchId)
+        {
+            if (string.IsNullOrWhiteSpace(matchId)) return null;
+            lock (_matchStatus) return _matchStatus.TryGetValue(matchId, out var status) ? status : null;
+        }
         private static List<SubmitRequest> LoadSubmitRequestsFromFile()
         {
             lock (_submitFileLock)
@@ -1514,8 +1681,6 @@ namespace LineraOrchestrator.Services
                         .ToList();
                 }
 
-                if (toRemove.Count == 0) return;
-
                 bool anyRemoved = false;
                 lock (_matchMap)
                 {
@@ -1533,6 +1698,29 @@ namespace LineraOrchestrator.Services
                 {
                     await Task.Run(() => SaveMatchMapping());
                 }
+
+                // Trạng thái submit thất bại của từng trận (_matchStatus)
+                bool anyStatusRemoved = false;
+                lock (_matchStatus)
+                {
+                    var failed = _matchStatus
+                        .Where(kv => string.Equals(kv.Value.Status, "submit failed", StringComparison.OrdinalIgnoreCase))
+                        .Select(kv => kv.Key)
+                        .ToList();
+                    foreach (var k in failed)
+                    {
+                        if (_matchStatus.Remove(k))
+                        {
+                            Console.WriteLine($"[CLEANUP] Removed failed match status: {k}");
+                            anyStatusRemoved = true;
+                        }
+                    }
+                }
+
+                if (anyStatusRemoved)
+                {
+                    await Task.Run(() => SaveMatchStatus());
+                }
             }
             catch (Exception ex)
             {